@@ -1,23 +1,37 @@
 mod base64;
+mod codec;
 mod csv;
 mod genpass;
+mod hash;
 mod http;
 mod jwt;
+mod key;
+mod otp;
 mod text;
 
 use enum_dispatch::enum_dispatch;
+use secrecy::SecretString;
+use std::convert::Infallible;
 use std::path::{Path, PathBuf};
 
 // 重新导出子模块的公共类型，提供统一接口
 pub use base64::{Base64DecodeOpts, Base64EncodeOpts, Base64Format, Base64SubCommand};
 use clap::Parser;
+pub use codec::{Codec, DecodeOpts, EncodeOpts};
 pub use csv::{CsvOpts, OutputFormat};
-pub use genpass::GenPassOpts;
+pub use genpass::{GenPassDeriveOpts, GenPassOpts, GenPassSubCommand};
+pub use hash::{HashAlgorithm, HashOpts};
 pub use http::{HttpServeOpts, HttpSubCommand};
-pub use jwt::{JwtSignOpts, JwtSubCommand, JwtVerifyOpts};
+pub use jwt::{JwtSecretOpts, JwtSignOpts, JwtSubCommand, JwtVerifyOpts};
+pub use key::{KeyAddOpts, KeyExportOpts, KeyListOpts, KeyRemoveOpts, KeySubCommand};
+pub use otp::{
+    OtpAlgorithm, OtpGenerateOpts, OtpMode, OtpSecretNewOpts, OtpSecretSubCommand, OtpSubCommand,
+    OtpVerifyOpts,
+};
 pub use text::{
-    TextDecryptOpts, TextEncryptOpts, TextKeyGenerateOpts, TextSignFormat, TextSignOpts,
-    TextSubCommand, TextVerifyOpts,
+    KeyEncoding, KeyFormat, PassphraseOpts, TextCipher, TextDecryptOpts, TextEncryptOpts,
+    TextKeyAgeKeygenOpts, TextKeyConvertOpts, TextKeyGenerateOpts, TextKeyPasswdOpts,
+    TextKeySubCommand, TextSignFormat, TextSignOpts, TextSubCommand, TextVerifyOpts,
 };
 
 #[derive(Debug, Parser)]
@@ -42,12 +56,28 @@ pub enum SubCommand {
     GenPass(GenPassOpts),
     #[command(subcommand, about = "Base64 encode/decode")]
     Base64(Base64SubCommand),
+    #[command(
+        name = "encode",
+        about = "Encode input with hex/base32/base58/ascii85 and more"
+    )]
+    Encode(EncodeOpts),
+    #[command(
+        name = "decode",
+        about = "Decode input with hex/base32/base58/ascii85 and more"
+    )]
+    Decode(DecodeOpts),
+    #[command(name = "hash", about = "Compute or verify file checksums")]
+    Hash(HashOpts),
     #[command(subcommand, about = "Text sign/verify")]
     Text(TextSubCommand),
+    #[command(subcommand, about = "Manage keys in the local keyring")]
+    Key(KeySubCommand),
     #[command(subcommand, about = "HTTP server")]
     Http(HttpSubCommand),
     #[command(subcommand, about = "JWT sign/verify")]
     Jwt(JwtSubCommand),
+    #[command(subcommand, about = "TOTP/HOTP one-time passwords")]
+    Otp(OtpSubCommand),
 }
 
 pub fn verify_file(filename: &str) -> Result<String, String> {
@@ -67,6 +97,11 @@ fn verify_path(path: &str) -> Result<PathBuf, &'static str> {
     }
 }
 
+/// 口令、密钥、明文等敏感参数解析为 `SecretString`，`Debug` 输出时显示为 `[REDACTED]`
+fn parse_secret(value: &str) -> Result<SecretString, Infallible> {
+    Ok(value.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,4 +114,41 @@ mod tests {
             Err("文件 'nonexistent' 不存在".into())
         );
     }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let opts = Opts::try_parse_from([
+            "rst",
+            "text",
+            "encrypt",
+            "--key",
+            "hunter2",
+            "--text",
+            "top secret",
+        ])
+        .unwrap();
+        let debug = format!("{:?}", opts);
+        assert!(!debug.contains("hunter2") && !debug.contains("top secret"));
+
+        let opts = Opts::try_parse_from([
+            "rst", "jwt", "verify", "-t", "token", "--aud", "a", "--secret", "hunter2",
+        ])
+        .unwrap();
+        assert!(!format!("{:?}", opts).contains("hunter2"));
+
+        let opts = Opts::try_parse_from([
+            "rst", "genpass", "derive", "--site", "a", "--user", "b", "--master", "hunter2",
+        ])
+        .unwrap();
+        assert!(!format!("{:?}", opts).contains("hunter2"));
+
+        for cmd in ["generate", "verify"] {
+            let mut args = vec!["rst", "otp", cmd, "--secret", "GEZDGNBVGY3TQOJQ"];
+            if cmd == "verify" {
+                args.extend(["--code", "123456"]);
+            }
+            let opts = Opts::try_parse_from(args).unwrap();
+            assert!(!format!("{:?}", opts).contains("GEZDGNBVGY3TQOJQ"));
+        }
+    }
 }
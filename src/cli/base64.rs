@@ -21,6 +21,43 @@ pub struct Base64EncodeOpts {
     pub input: String,
     #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
     pub format: Base64Format,
+    #[arg(short, long, help = "Write output to a file instead of stdout")]
+    pub output: Option<String>,
+    #[arg(long, help = "Do not append a trailing newline")]
+    pub raw: bool,
+    #[arg(long, help = "Omit trailing '=' padding")]
+    pub no_pad: bool,
+    #[arg(
+        long,
+        value_name = "COLS",
+        help = "Wrap output lines at COLS characters"
+    )]
+    pub wrap: Option<usize>,
+    #[arg(
+        long,
+        conflicts_with = "wrap",
+        help = "MIME style output: 76-column lines separated by CRLF"
+    )]
+    pub mime: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["wrap", "mime", "pem"],
+        help = "Emit a data: URI, sniffing the media type from content or file extension"
+    )]
+    pub data_uri: bool,
+    #[arg(
+        long,
+        requires = "data_uri",
+        help = "Media type to use instead of sniffing"
+    )]
+    pub mime_type: Option<String>,
+    #[arg(
+        long,
+        value_name = "LABEL",
+        conflicts_with_all = ["wrap", "mime"],
+        help = "Wrap output in -----BEGIN LABEL----- / -----END LABEL----- armor"
+    )]
+    pub pem: Option<String>,
 }
 
 #[derive(Debug, Parser)]
@@ -29,9 +66,25 @@ pub struct Base64DecodeOpts {
     pub input: String,
     #[arg(long, default_value = "standard")]
     pub format: Base64Format,
+    #[arg(short, long, help = "Write decoded bytes to a file instead of stdout")]
+    pub output: Option<String>,
+    #[arg(long, help = "Do not append a trailing newline to decoded text")]
+    pub raw: bool,
+    #[arg(long, help = "Write binary output to the terminal anyway")]
+    pub force: bool,
+    #[arg(
+        long,
+        help = "Ignore all whitespace and accept input with or without padding"
+    )]
+    pub lenient: bool,
+    #[arg(
+        long,
+        help = "Guess whether the input uses the standard or URL-safe alphabet (implies --lenient)"
+    )]
+    pub detect: bool,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Base64Format {
     Standard,
     UrlSafe,
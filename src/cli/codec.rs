@@ -0,0 +1,99 @@
+use super::verify_file;
+use crate::CmdExecutor;
+use clap::Parser;
+use std::fmt;
+use std::str::FromStr;
+
+// cargo run -- encode --codec base58 -i Cargo.toml
+// echo 68656c6c6f | cargo run -- decode --codec hex
+
+#[derive(Debug, Parser)]
+pub struct EncodeOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    #[arg(short, long, default_value = "hex")]
+    pub codec: Codec,
+    #[arg(short, long, help = "Write output to a file instead of stdout")]
+    pub output: Option<String>,
+    #[arg(long, help = "Do not append a trailing newline")]
+    pub raw: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct DecodeOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    #[arg(short, long, default_value = "hex")]
+    pub codec: Codec,
+    #[arg(short, long, help = "Write decoded bytes to a file instead of stdout")]
+    pub output: Option<String>,
+    #[arg(long, help = "Do not append a trailing newline to decoded text")]
+    pub raw: bool,
+    #[arg(long, help = "Write binary output to the terminal anyway")]
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Hex,
+    Base32,
+    Base32Crockford,
+    ZBase32,
+    Base58,
+    Base64,
+    Base64Url,
+    Ascii85,
+}
+
+impl FromStr for Codec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" | "base16" => Ok(Codec::Hex),
+            "base32" => Ok(Codec::Base32),
+            "base32-crockford" | "crockford" => Ok(Codec::Base32Crockford),
+            "z-base-32" | "zbase32" => Ok(Codec::ZBase32),
+            "base58" => Ok(Codec::Base58),
+            "base64" => Ok(Codec::Base64),
+            "base64url" => Ok(Codec::Base64Url),
+            "ascii85" | "base85" => Ok(Codec::Ascii85),
+            _ => Err(anyhow::anyhow!("Invalid codec")),
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<Codec> for &str {
+    fn from(codec: Codec) -> Self {
+        match codec {
+            Codec::Hex => "hex",
+            Codec::Base32 => "base32",
+            Codec::Base32Crockford => "base32-crockford",
+            Codec::ZBase32 => "z-base-32",
+            Codec::Base58 => "base58",
+            Codec::Base64 => "base64",
+            Codec::Base64Url => "base64url",
+            Codec::Ascii85 => "ascii85",
+        }
+    }
+}
+
+impl CmdExecutor for EncodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_codec_encode(&self)?;
+        Ok(())
+    }
+}
+
+impl CmdExecutor for DecodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_codec_decode(&self)?;
+        Ok(())
+    }
+}
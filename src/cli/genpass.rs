@@ -1,36 +1,96 @@
+use super::parse_secret;
 use crate::CmdExecutor;
 use clap::Parser;
+use secrecy::SecretString;
 use zxcvbn::zxcvbn;
 
 #[derive(Debug, Parser)]
 pub struct GenPassOpts {
-    #[arg(short, long, default_value_t = 12)]
+    #[command(subcommand)]
+    pub cmd: Option<GenPassSubCommand>,
+    #[arg(short, long, global = true, default_value_t = 12)]
     pub length: usize,
-    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, value_parser = clap::value_parser!(bool))]
+    #[arg(long, global = true, default_value_t = true, action = clap::ArgAction::Set, value_parser = clap::value_parser!(bool))]
     pub uppercase: bool,
-    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, value_parser = clap::value_parser!(bool))]
+    #[arg(long, global = true, default_value_t = true, action = clap::ArgAction::Set, value_parser = clap::value_parser!(bool))]
     pub lowercase: bool,
-    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, value_parser = clap::value_parser!(bool))]
+    #[arg(long, global = true, default_value_t = true, action = clap::ArgAction::Set, value_parser = clap::value_parser!(bool))]
     pub numbers: bool,
-    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, value_parser = clap::value_parser!(bool))]
+    #[arg(long, global = true, default_value_t = true, action = clap::ArgAction::Set, value_parser = clap::value_parser!(bool))]
     pub symbols: bool,
+    #[arg(
+        long,
+        conflicts_with = "pattern",
+        help = "Generate consonant-vowel syllables that are easy to read aloud"
+    )]
+    pub pronounceable: bool,
+    #[arg(
+        long,
+        help = "Template such as \"Cvccvc-99-Cvccvc\" (C/c consonant, V/v vowel, A/a letter, 9 digit, # symbol, * any, \\ escapes)"
+    )]
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub enum GenPassSubCommand {
+    #[command(about = "Derive a stable password from a master passphrase")]
+    Derive(GenPassDeriveOpts),
+}
+
+// cargo run -- genpass derive --site example.com --user alice --counter 1 --master "correct horse"
+
+#[derive(Debug, Parser)]
+pub struct GenPassDeriveOpts {
+    #[arg(long, help = "Site or service the password belongs to")]
+    pub site: String,
+    #[arg(
+        long,
+        help = "Account name on the site (case-sensitive; the site is not)"
+    )]
+    pub user: String,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Bump to rotate the derived password"
+    )]
+    pub counter: u32,
+    #[arg(
+        long,
+        value_parser = parse_secret,
+        help = "Master passphrase. Falls back to RST_MASTER_PASSWORD env var when omitted"
+    )]
+    pub master: Option<SecretString>,
+    #[arg(long, default_value_t = 65536, help = "Argon2id memory cost in KiB")]
+    pub memory: u32,
+    #[arg(long, default_value_t = 3, help = "Argon2id iterations")]
+    pub iterations: u32,
+    #[arg(long, default_value_t = 1, help = "Argon2id parallelism")]
+    pub parallelism: u32,
 }
 
 impl GenPassOpts {
     pub fn get_default_opts() -> Self {
         Self {
+            cmd: None,
             length: 12,
             uppercase: true,
             lowercase: true,
             numbers: true,
             symbols: true,
+            pronounceable: false,
+            pattern: None,
         }
     }
 }
 
 impl CmdExecutor for GenPassOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let password = crate::process_genpass(&self)?;
+        let password = match &self.cmd {
+            Some(GenPassSubCommand::Derive(derive)) => {
+                crate::process_genpass_derive(&self, derive)?
+            }
+            None => crate::process_genpass(&self)?,
+        };
         println!("{}", password);
         let score = zxcvbn(&password, &[]).score();
         eprintln!("密码强度评分：{}/5", score);
@@ -0,0 +1,138 @@
+use super::verify_file;
+use crate::{process_hash, process_hash_check, CmdExecutor};
+use clap::Parser;
+use std::{fmt, str::FromStr, thread};
+
+// cargo run -- hash -a sha256 Cargo.toml Cargo.lock > SHA256SUMS
+// cargo run -- hash --check SHA256SUMS
+
+#[derive(Debug, Parser)]
+pub struct HashOpts {
+    #[arg(value_parser = verify_file, default_value = "-", help = "Files to hash ('-' for stdin)")]
+    pub inputs: Vec<String>,
+    #[arg(
+        short,
+        long,
+        help = "Digest algorithm (defaults to blake3; with --check, inferred from the SUMS file name or digest length)"
+    )]
+    pub algorithm: Option<HashAlgorithm>,
+    #[arg(
+        short,
+        long,
+        value_name = "SUMS",
+        value_parser = verify_file,
+        conflicts_with = "inputs",
+        help = "Verify checksums listed in a `sha256sum`-style file"
+    )]
+    pub check: Option<String>,
+    #[arg(
+        short,
+        long,
+        help = "Number of files hashed in parallel (defaults to CPU count)"
+    )]
+    pub jobs: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Blake3,
+    Sha256,
+    Sha512,
+    Sha3_256,
+    Sha3_512,
+    Sha1,
+    Md5,
+}
+
+impl HashOpts {
+    pub fn jobs(&self) -> usize {
+        self.jobs
+            .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+            .unwrap_or(1)
+            .max(1)
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<HashAlgorithm> for &str {
+    fn from(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Sha3_256 => "sha3-256",
+            HashAlgorithm::Sha3_512 => "sha3-512",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Md5 => "md5",
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            "sha3-256" => Ok(HashAlgorithm::Sha3_256),
+            "sha3-512" => Ok(HashAlgorithm::Sha3_512),
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "md5" => Ok(HashAlgorithm::Md5),
+            _ => Err(anyhow::anyhow!("Invalid algorithm")),
+        }
+    }
+}
+
+impl CmdExecutor for HashOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if let Some(sums) = &self.check {
+            let results = process_hash_check(sums, self.algorithm, self.jobs())?;
+            let mut failed = 0;
+            for (name, result) in &results {
+                match result {
+                    Ok(true) => println!("{}: OK", name),
+                    Ok(false) => {
+                        failed += 1;
+                        println!("{}: FAILED", name);
+                    }
+                    Err(e) => {
+                        failed += 1;
+                        println!("{}: FAILED open or read", name);
+                        eprintln!("{}: {:#}", name, e);
+                    }
+                }
+            }
+            anyhow::ensure!(
+                failed == 0,
+                "{} of {} computed checksums did NOT match",
+                failed,
+                results.len()
+            );
+            return Ok(());
+        }
+
+        let mut failed = false;
+        for (name, digest) in self.inputs.iter().zip(process_hash(
+            &self.inputs,
+            self.algorithm.unwrap_or(HashAlgorithm::Blake3),
+            self.jobs(),
+        )) {
+            match digest {
+                Ok(digest) => println!("{}  {}", digest, name),
+                Err(e) => {
+                    failed = true;
+                    eprintln!("{}: {:#}", name, e);
+                }
+            }
+        }
+        anyhow::ensure!(!failed, "Some inputs could not be hashed");
+        Ok(())
+    }
+}
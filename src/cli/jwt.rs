@@ -1,6 +1,8 @@
-use crate::CmdExecutor;
-use clap::Parser;
+use super::parse_secret;
+use crate::{CmdExecutor, SecretSource};
+use clap::{Args, Parser};
 use enum_dispatch::enum_dispatch;
+use secrecy::SecretString;
 
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExecutor)]
@@ -23,11 +25,8 @@ pub struct JwtSignOpts {
         default_value = "14d"
     )]
     pub exp: String,
-    #[arg(
-        long,
-        help = "HS256 shared secret. Falls back to JWT_SECRET env var when omitted"
-    )]
-    pub secret: Option<String>,
+    #[command(flatten)]
+    pub secret: JwtSecretOpts,
 }
 
 #[derive(Debug, Parser)]
@@ -36,11 +35,48 @@ pub struct JwtVerifyOpts {
     pub token: String,
     #[arg(long, help = "Expected audience (aud claim)")]
     pub aud: String,
+    #[command(flatten)]
+    pub secret: JwtSecretOpts,
+}
+
+// JWT_SECRET=... cargo run -- jwt sign --sub acme --aud device1
+// cargo run -- jwt verify -t <TOKEN> --aud device1 --secret-file secret.txt
+
+/// HS256 共享密钥来源，至多指定一个；均未指定时依次使用 JWT_SECRET 环境变量与终端提示（不回显）
+#[derive(Debug, Default, Clone, Args)]
+#[group(id = "secret_source", multiple = false)]
+pub struct JwtSecretOpts {
     #[arg(
         long,
-        help = "HS256 shared secret. Falls back to JWT_SECRET env var when omitted"
+        value_parser = parse_secret,
+        help = "HS256 shared secret (visible in shell history and ps; prefer the options below)"
     )]
-    pub secret: Option<String>,
+    pub secret: Option<SecretString>,
+    #[arg(long, help = "Read the secret from the first line of a file")]
+    pub secret_file: Option<String>,
+    #[arg(
+        long,
+        value_name = "VAR",
+        help = "Read the secret from an environment variable"
+    )]
+    pub secret_env: Option<String>,
+    #[arg(
+        long,
+        value_name = "FD",
+        help = "Read the secret from an open file descriptor, e.g. --secret-fd 3 3<secret.txt"
+    )]
+    pub secret_fd: Option<i32>,
+}
+
+impl From<JwtSecretOpts> for SecretSource {
+    fn from(opts: JwtSecretOpts) -> Self {
+        SecretSource::from_options(
+            opts.secret,
+            opts.secret_file,
+            opts.secret_env,
+            opts.secret_fd,
+        )
+    }
 }
 
 impl CmdExecutor for JwtSignOpts {
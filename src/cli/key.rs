@@ -0,0 +1,145 @@
+use super::verify_file;
+use crate::{
+    get_input_bytes, get_writer, process_text_key_convert, unlock_key, write_secret_file,
+    CmdExecutor, KeyFormat, Keyring, TextSignFormat,
+};
+use anyhow::ensure;
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::io::Write;
+
+// cargo run -- key add fixtures/ed25519.sk --name release --format ed25519
+// cargo run -- key add fixtures/ed25519.pk --name release.pub --format ed25519 --public
+// cargo run -- text sign -i Cargo.toml --key-id release --format ed25519
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum KeySubCommand {
+    #[command(about = "List keys in the keyring")]
+    List(KeyListOpts),
+    #[command(about = "Import a key file into the keyring")]
+    Add(KeyAddOpts),
+    #[command(about = "Remove a key by name or fingerprint")]
+    Remove(KeyRemoveOpts),
+    #[command(about = "Export a key by name or fingerprint")]
+    Export(KeyExportOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyListOpts {}
+
+#[derive(Debug, Parser)]
+pub struct KeyAddOpts {
+    #[arg(value_parser = verify_file, help = "Key file to import ('-' for stdin)")]
+    pub input: String,
+    #[arg(short, long, help = "Name used to address the key")]
+    pub name: String,
+    #[arg(
+        short,
+        long,
+        default_value = "ed25519",
+        help = "Key algorithm: blake3, hmac-sha256, hmac-sha512, ed25519, ecdsa-p256 or ecdsa-secp256k1"
+    )]
+    pub format: TextSignFormat,
+    #[arg(long, help = "The file holds an Ed25519 or ECDSA public key")]
+    pub public: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyRemoveOpts {
+    #[arg(help = "Key name or fingerprint prefix")]
+    pub key: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyExportOpts {
+    #[arg(help = "Key name or fingerprint prefix")]
+    pub key: String,
+    #[arg(long, help = "Export only the public key of an Ed25519 key")]
+    pub public: bool,
+    #[arg(long, help = "Convert an Ed25519 key to raw, pem, openssh or jwk")]
+    pub to: Option<KeyFormat>,
+    #[arg(short, long, help = "Write the key to a file instead of stdout")]
+    pub output: Option<String>,
+}
+
+impl CmdExecutor for KeyListOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let keyring = Keyring::open()?;
+        let entries = keyring.list()?;
+        if entries.is_empty() {
+            eprintln!("Keyring {} is empty", keyring.dir().display());
+            return Ok(());
+        }
+        println!(
+            "{:<24} {:<15} {:<7} FINGERPRINT",
+            "NAME", "ALGORITHM", "TYPE"
+        );
+        for entry in entries {
+            let kind = if entry.secret { "secret" } else { "public" };
+            println!(
+                "{:<24} {:<15} {:<7} {}",
+                entry.name, entry.algorithm, kind, entry.fingerprint
+            );
+        }
+        Ok(())
+    }
+}
+
+impl CmdExecutor for KeyAddOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let symmetric = matches!(
+            self.format,
+            TextSignFormat::Blake3 | TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512
+        );
+        ensure!(
+            !(self.public && symmetric),
+            "{} keys are symmetric and have no public half",
+            self.format
+        );
+        let data = get_input_bytes(&self.input)?;
+        let entry = Keyring::open()?.add(&self.name, &data, self.format, !self.public)?;
+        eprintln!("Added key '{}' ({})", entry.name, entry.fingerprint);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for KeyRemoveOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let entry = Keyring::open()?.remove(&self.key)?;
+        eprintln!("Removed key '{}' ({})", entry.name, entry.fingerprint);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for KeyExportOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let keyring = Keyring::open()?;
+        let entry = keyring.find(&self.key, Some(!self.public))?;
+        let mut data = keyring.load(&entry)?;
+        if self.public || self.to.is_some() {
+            ensure!(
+                entry.algorithm == "ed25519",
+                "Only Ed25519 keys can be converted"
+            );
+            data = process_text_key_convert(
+                &unlock_key(data)?,
+                self.to.unwrap_or(KeyFormat::Raw),
+                !entry.secret,
+                self.public,
+                "",
+            )?;
+        }
+
+        let secret = entry.secret && !self.public;
+        match self.output.as_deref() {
+            Some(path) if path != "-" && secret => write_secret_file(path, &data)?,
+            output => {
+                let mut writer = get_writer(output)?;
+                writer.write_all(&data)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use super::parse_secret;
+use crate::{process_otp_generate, process_otp_secret_new, process_otp_verify, CmdExecutor};
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use secrecy::SecretString;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum OtpSubCommand {
+    #[command(about = "Generate a TOTP/HOTP code")]
+    Generate(OtpGenerateOpts),
+    #[command(about = "Verify a TOTP/HOTP code")]
+    Verify(OtpVerifyOpts),
+    #[command(subcommand, about = "Manage OTP secrets")]
+    Secret(OtpSecretSubCommand),
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum OtpSecretSubCommand {
+    #[command(about = "Generate a new base32 secret and otpauth:// URI")]
+    New(OtpSecretNewOpts),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpMode {
+    Totp,
+    Hotp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+// cargo run -- otp generate --secret GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ --time 59 --digits 8
+
+#[derive(Debug, Parser)]
+pub struct OtpGenerateOpts {
+    #[arg(short, long, value_parser = parse_secret, help = "Shared secret in base32")]
+    pub secret: SecretString,
+    #[arg(long, default_value = "totp")]
+    pub mode: OtpMode,
+    #[arg(long, default_value = "sha1")]
+    pub algorithm: OtpAlgorithm,
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=8))]
+    pub digits: u32,
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..), help = "TOTP time step in seconds")]
+    pub period: u64,
+    #[arg(
+        long,
+        help = "Unix timestamp to use instead of the current time (TOTP)"
+    )]
+    pub time: Option<u64>,
+    #[arg(long, help = "Moving factor (required for HOTP)")]
+    pub counter: Option<u64>,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpVerifyOpts {
+    #[arg(short, long, value_parser = parse_secret, help = "Shared secret in base32")]
+    pub secret: SecretString,
+    #[arg(long, help = "Code to verify")]
+    pub code: String,
+    #[arg(long, default_value = "totp")]
+    pub mode: OtpMode,
+    #[arg(long, default_value = "sha1")]
+    pub algorithm: OtpAlgorithm,
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=8))]
+    pub digits: u32,
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..), help = "TOTP time step in seconds")]
+    pub period: u64,
+    #[arg(
+        long,
+        help = "Unix timestamp to use instead of the current time (TOTP)"
+    )]
+    pub time: Option<u64>,
+    #[arg(long, help = "Moving factor (required for HOTP)")]
+    pub counter: Option<u64>,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Accepted drift: TOTP steps before/after now, or HOTP look-ahead counters"
+    )]
+    pub window: u64,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpSecretNewOpts {
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u8).range(10..=64), help = "Secret length in bytes")]
+    pub bytes: u8,
+    #[arg(long, help = "Issuer shown by authenticator apps")]
+    pub issuer: Option<String>,
+    #[arg(
+        long,
+        default_value = "user",
+        help = "Account name shown by authenticator apps"
+    )]
+    pub account: String,
+    #[arg(long, default_value = "totp")]
+    pub mode: OtpMode,
+    #[arg(long, default_value = "sha1")]
+    pub algorithm: OtpAlgorithm,
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=8))]
+    pub digits: u32,
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..), help = "TOTP time step in seconds")]
+    pub period: u64,
+}
+
+impl fmt::Display for OtpMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<OtpMode> for &str {
+    fn from(mode: OtpMode) -> Self {
+        match mode {
+            OtpMode::Totp => "totp",
+            OtpMode::Hotp => "hotp",
+        }
+    }
+}
+
+impl FromStr for OtpMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "totp" => Ok(OtpMode::Totp),
+            "hotp" => Ok(OtpMode::Hotp),
+            _ => Err(anyhow::anyhow!("Invalid mode")),
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<OtpAlgorithm> for &str {
+    fn from(algorithm: OtpAlgorithm) -> Self {
+        match algorithm {
+            OtpAlgorithm::Sha1 => "sha1",
+            OtpAlgorithm::Sha256 => "sha256",
+            OtpAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha1" => Ok(OtpAlgorithm::Sha1),
+            "sha256" => Ok(OtpAlgorithm::Sha256),
+            "sha512" => Ok(OtpAlgorithm::Sha512),
+            _ => Err(anyhow::anyhow!("Invalid algorithm")),
+        }
+    }
+}
+
+impl CmdExecutor for OtpGenerateOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let code = process_otp_generate(&self)?;
+        println!("{}", code);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for OtpVerifyOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let valid = process_otp_verify(&self)?;
+        println!("{}", valid);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for OtpSecretNewOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let (secret, uri) = process_otp_secret_new(&self)?;
+        println!("{}", secret);
+        println!("{}", uri);
+        Ok(())
+    }
+}
@@ -1,22 +1,31 @@
-use super::{verify_file, verify_path};
+use super::{parse_secret, verify_file, verify_path};
 use crate::{
-    get_input_bytes, get_reader, process_text_decrypt, process_text_encrypt,
-    process_text_key_generate, process_text_sign, process_text_verify, CmdExecutor,
+    get_input_bytes, get_reader, get_writer, is_age_file, key_pair_files, parse_identities,
+    parse_recipient, parse_recipients_file, process_text_age_decrypt, process_text_age_encrypt,
+    process_text_age_keygen, process_text_decrypt, process_text_decrypt_stream,
+    process_text_encrypt, process_text_encrypt_stream, process_text_key_convert,
+    process_text_key_fingerprint, process_text_key_generate, process_text_sign,
+    process_text_sign_detached, process_text_sign_minisign, process_text_verify,
+    process_text_verify_detached, process_text_verify_minisign, protect_key, read_passphrase,
+    unlock_key, write_secret_file, CmdExecutor, DetachedSignature, EncryptionKey, KdfParams,
+    Keyring, SecretSource, NEW_PASSPHRASE_ENV, PASSPHRASE_ENV,
 };
-use anyhow::{ensure, Context};
+use anyhow::{bail, ensure, Context};
 use base64::{
     engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD},
     Engine,
 };
-use clap::Parser;
+use clap::{Args, Parser};
 use enum_dispatch::enum_dispatch;
+use secrecy::{ExposeSecret, SecretString};
 use std::{
     fmt,
-    io::{Cursor, Read},
-    path::PathBuf,
+    io::{BufWriter, Cursor, Read, Write},
+    path::{Path, PathBuf},
     str::FromStr,
 };
 use tokio::fs;
+use zeroize::Zeroizing;
 
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExecutor)]
@@ -27,16 +36,62 @@ pub enum TextSubCommand {
     Verify(TextVerifyOpts),
     #[command(about = "Generate a new key")]
     Generate(TextKeyGenerateOpts),
-    #[command(about = "Encrypt input with ChaCha20-Poly1305")]
+    #[command(about = "Encrypt input with a passphrase, raw key or age recipients")]
     Encrypt(TextEncryptOpts),
-    #[command(about = "Decrypt input with ChaCha20-Poly1305")]
+    #[command(about = "Decrypt rst containers or age files")]
     Decrypt(TextDecryptOpts),
+    #[command(
+        subcommand,
+        about = "Convert and protect key files, and generate age identities"
+    )]
+    Key(TextKeySubCommand),
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum TextKeySubCommand {
+    #[command(about = "Convert an Ed25519 key between raw, PEM, OpenSSH and JWK")]
+    Convert(TextKeyConvertOpts),
+    #[command(about = "Add, change or remove the passphrase of a key file")]
+    Passwd(TextKeyPasswdOpts),
+    #[command(about = "Generate an X25519 identity for encrypting to recipients (age format)")]
+    AgeKeygen(TextKeyAgeKeygenOpts),
+}
+
+/// Ed25519 密钥文件的编码格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyFormat {
+    Raw,
+    Pem,
+    OpenSsh,
+    Jwk,
+}
+
+/// Blake3 密钥在磁盘上的编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEncoding {
+    Hex,
+    Base64,
+    Raw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextCipher {
+    ChaCha20Poly1305,
+    XChaCha20Poly1305,
+    Aes256Gcm,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextSignFormat {
     Blake3,
     Ed25519,
+    Ed25519ph,
+    Minisign,
+    HmacSha256,
+    HmacSha512,
+    EcdsaP256,
+    EcdsaSecp256k1,
 }
 
 #[derive(Debug, Parser)]
@@ -44,22 +99,55 @@ pub struct TextSignOpts {
     // default_value = "-" 表示从stdin读取
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
-    #[arg(short, long, value_parser = verify_file)]
-    pub key: String,
+    #[arg(short, long, value_parser = verify_file, required_unless_present = "key_id")]
+    pub key: Option<String>,
+    #[arg(
+        long,
+        conflicts_with = "key",
+        help = "Name or fingerprint of a key in the keyring"
+    )]
+    pub key_id: Option<String>,
     #[arg(long, default_value = "blake3")]
     pub format: TextSignFormat,
+    #[arg(
+        long,
+        help = "Write a JSON detached signature file (defaults to <input>.sig)"
+    )]
+    pub detached: bool,
+    #[arg(short, long, help = "Path of the detached or .minisig signature file")]
+    pub output: Option<String>,
+    #[arg(long, help = "Trusted comment for minisign signatures")]
+    pub comment: Option<String>,
+    #[arg(long, help = "Produce a legacy (non-prehashed) minisign signature")]
+    pub legacy: bool,
 }
 
 #[derive(Debug, Parser)]
 pub struct TextVerifyOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
-    #[arg(short, long, value_parser = verify_file)]
-    pub key: String,
-    #[arg(long)]
-    pub sig: String,
-    #[arg(long, default_value = "blake3")]
-    pub format: TextSignFormat,
+    #[arg(short, long, value_parser = verify_file, required_unless_present = "key_id")]
+    pub key: Option<String>,
+    #[arg(
+        long,
+        conflicts_with = "key",
+        help = "Name or fingerprint of a key in the keyring"
+    )]
+    pub key_id: Option<String>,
+    #[arg(long, help = "Bare URL-safe Base64 signature")]
+    pub sig: Option<String>,
+    #[arg(
+        long,
+        value_parser = verify_file,
+        conflicts_with = "sig",
+        help = "Detached signature file (defaults to <input>.sig when --sig is omitted)"
+    )]
+    pub sig_file: Option<String>,
+    #[arg(
+        long,
+        help = "Expected algorithm (defaults to blake3 for --sig, or the one recorded in the signature file)"
+    )]
+    pub format: Option<TextSignFormat>,
 }
 
 #[derive(Debug, Parser)]
@@ -68,36 +156,258 @@ pub struct TextKeyGenerateOpts {
     pub format: TextSignFormat,
     #[arg(short, long, value_parser = verify_path)]
     pub output: PathBuf,
+    #[arg(
+        long,
+        default_value = "raw",
+        help = "Key file format: raw, pem, openssh or jwk (ECDSA: raw or pem)"
+    )]
+    pub key_format: KeyFormat,
+    #[arg(
+        long,
+        default_value = "hex",
+        help = "Blake3 key encoding: hex, base64 or raw (32 bytes)"
+    )]
+    pub encoding: KeyEncoding,
+    #[arg(
+        long,
+        help = "Encrypt the secret key with a passphrase (prompted, or RST_PASSPHRASE)"
+    )]
+    pub protect: bool,
 }
+
+// cargo run -- text key convert -i fixtures/ed25519.sk --to openssh
+// cargo run -- text key convert -i fixtures/ed25519.pem --to jwk --public
+
+#[derive(Debug, Parser)]
+pub struct TextKeyConvertOpts {
+    #[arg(
+        short,
+        long,
+        value_parser = verify_file,
+        default_value = "-",
+        help = "Key to convert (raw keys named *.pk or *.pub are read as public keys)"
+    )]
+    pub input: String,
+    #[arg(long, help = "Target format: raw, pem, openssh or jwk")]
+    pub to: KeyFormat,
+    #[arg(long, help = "Export only the public key")]
+    pub public: bool,
+    #[arg(long, default_value = "", help = "Comment for OpenSSH keys")]
+    pub comment: String,
+    #[arg(
+        short,
+        long,
+        help = "Write the converted key to a file instead of stdout"
+    )]
+    pub output: Option<String>,
+}
+
+// RST_NEW_PASSPHRASE=... cargo run -- text key passwd -i ed25519.sk
+
+#[derive(Debug, Parser)]
+pub struct TextKeyPasswdOpts {
+    #[arg(short, long, value_parser = verify_file, help = "Key file to update in place")]
+    pub input: String,
+    #[arg(long, help = "Remove the passphrase and store the key in plaintext")]
+    pub remove: bool,
+}
+
 // cargo run -- text encrypt --key "hello" --text "hello world"
-// cargo run -- text decrypt --key "hello" --cipher  ETzc+ijUW2Yb0Nvf0HzZthdDc+FoyY/+hWpvPXK/dsf90cIGwagQ
+// cargo run -- text decrypt --key "hello" --ciphertext <BASE64>
+// cargo run -- text encrypt --key "hello" --cipher aes256gcm --text "hello world"
+// head -c 32 /dev/urandom > chacha.key && cargo run -- text encrypt --raw-key chacha.key --text "hi"
 
 #[derive(Debug, Parser)]
 pub struct TextEncryptOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
+    /// 口令经 Argon2id（随机盐）拉伸为 32 字节密钥；未指定任何密钥时在终端提示输入并确认
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
+    #[arg(
+        long,
+        value_parser = verify_file,
+        conflicts_with = "passphrase",
+        help = "File holding a raw 32-byte key (binary or hex), used without KDF"
+    )]
+    pub raw_key: Option<String>,
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = ["passphrase", "raw_key"],
+        help = "Encrypt to an age1... or ssh-ed25519 recipient (age format, repeatable)"
+    )]
+    pub recipient: Vec<String>,
+    #[arg(
+        short = 'R',
+        long,
+        value_parser = verify_file,
+        conflicts_with_all = ["passphrase", "raw_key"],
+        help = "File of recipients, or an Ed25519 key file (repeatable)"
+    )]
+    pub recipients_file: Vec<String>,
+    #[arg(
+        long,
+        help = "Write age output as ASCII armor (always used when writing to stdout)"
+    )]
+    pub armor: bool,
+    #[arg(long, default_value_t = KdfParams::default().memory, help = "Argon2id memory cost in KiB")]
+    pub kdf_memory: u32,
+    #[arg(long, default_value_t = KdfParams::default().iterations, help = "Argon2id iterations")]
+    pub kdf_iterations: u32,
+    #[arg(long, default_value_t = KdfParams::default().parallelism, help = "Argon2id parallelism")]
+    pub kdf_parallelism: u32,
+    #[arg(
+        long,
+        alias = "algorithm",
+        default_value = "xchacha20poly1305",
+        help = "AEAD cipher: xchacha20poly1305, aes256gcm or chacha20poly1305"
+    )]
+    pub cipher: TextCipher,
     #[arg(
-        short = 'k',
         long,
-        help = "Symmetric key material (will be stretched to 32 bytes)"
+        conflicts_with_all = ["aad_file", "recipient", "recipients_file"],
+        help = "Associated data binding the ciphertext to a context (authenticated, not encrypted)"
     )]
-    pub key: String,
-    #[arg(long, help = "Plaintext to encrypt (overrides --input)")]
-    pub text: Option<String>,
+    pub aad: Option<String>,
+    #[arg(
+        long,
+        value_parser = verify_file,
+        conflicts_with_all = ["recipient", "recipients_file"],
+        help = "Read the associated data from a file"
+    )]
+    pub aad_file: Option<String>,
+    #[arg(long, value_parser = parse_secret, help = "Plaintext to encrypt (overrides --input)")]
+    pub text: Option<SecretString>,
+    #[arg(
+        short,
+        long,
+        help = "Stream-encrypt in 64 KiB segments and write binary output to this file"
+    )]
+    pub output: Option<String>,
 }
 
 #[derive(Debug, Parser)]
 pub struct TextDecryptOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
+    /// 加密时使用的口令；未指定任何密钥时在终端提示输入
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
     #[arg(
-        short = 'k',
         long,
-        help = "Symmetric key material (must match encryption)"
+        value_parser = verify_file,
+        conflicts_with = "passphrase",
+        help = "File holding the raw 32-byte key used for encryption"
     )]
-    pub key: String,
+    pub raw_key: Option<String>,
+    #[arg(
+        long,
+        value_parser = verify_file,
+        conflicts_with_all = ["passphrase", "raw_key"],
+        help = "age identity file or Ed25519 private key for age input (repeatable)"
+    )]
+    pub identity: Vec<String>,
+    #[arg(
+        long,
+        conflicts_with_all = ["aad_file", "identity"],
+        help = "Associated data the ciphertext was bound to"
+    )]
+    pub aad: Option<String>,
+    #[arg(
+        long,
+        value_parser = verify_file,
+        conflicts_with = "identity",
+        help = "Read the associated data from a file"
+    )]
+    pub aad_file: Option<String>,
     #[arg(long, help = "Ciphertext in Base64 (overrides --input)")]
-    pub cipher: Option<String>,
+    pub ciphertext: Option<String>,
+    #[arg(
+        long,
+        alias = "algorithm",
+        help = "Require this cipher (the algorithm is otherwise read from the ciphertext)"
+    )]
+    pub cipher: Option<TextCipher>,
+    #[arg(
+        short,
+        long,
+        conflicts_with = "ciphertext",
+        help = "Decrypt binary input as a stream and write the plaintext to this file"
+    )]
+    pub output: Option<String>,
+}
+
+// cargo run -- text encrypt --key-file pass.txt --text "hello world"
+// cargo run -- text decrypt --key-fd 3 --ciphertext <BASE64> 3<pass.txt
+
+/// 文本加解密的口令来源，至多指定一个；均未指定时在终端提示输入（不回显）
+#[derive(Debug, Default, Args)]
+#[group(id = "passphrase", multiple = false)]
+pub struct PassphraseOpts {
+    #[arg(
+        short = 'k',
+        long,
+        value_parser = parse_secret,
+        help = "Passphrase (visible in shell history and ps; prefer --key-file, --key-env or --key-fd)"
+    )]
+    pub key: Option<SecretString>,
+    #[arg(long, value_parser = verify_file, help = "Read the passphrase from the first line of a file")]
+    pub key_file: Option<String>,
+    #[arg(
+        long,
+        value_name = "VAR",
+        help = "Read the passphrase from an environment variable"
+    )]
+    pub key_env: Option<String>,
+    #[arg(
+        long,
+        value_name = "FD",
+        help = "Read the passphrase from an open file descriptor, e.g. --key-fd 3 3<pass.txt"
+    )]
+    pub key_fd: Option<i32>,
+}
+
+// cargo run -- text key age-keygen -o age.key
+
+#[derive(Debug, Parser)]
+pub struct TextKeyAgeKeygenOpts {
+    #[arg(short, long, help = "Write the identity to a file instead of stdout")]
+    pub output: Option<String>,
+}
+
+impl fmt::Display for TextCipher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<TextCipher> for &str {
+    fn from(cipher: TextCipher) -> Self {
+        match cipher {
+            TextCipher::ChaCha20Poly1305 => "chacha20poly1305",
+            TextCipher::XChaCha20Poly1305 => "xchacha20poly1305",
+            TextCipher::Aes256Gcm => "aes256gcm",
+        }
+    }
+}
+
+impl FromStr for TextCipher {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chacha20poly1305" | "chacha20" => Ok(TextCipher::ChaCha20Poly1305),
+            "xchacha20poly1305" | "xchacha20" => Ok(TextCipher::XChaCha20Poly1305),
+            "aes256gcm" | "aes-256-gcm" => Ok(TextCipher::Aes256Gcm),
+            // 旧版本的 `text decrypt --cipher` 接收的是密文，给出迁移提示
+            _ => Err(anyhow::anyhow!(
+                "Invalid cipher '{}': expected xchacha20poly1305, aes256gcm or chacha20poly1305 \
+                 (Base64 ciphertext is now passed with --ciphertext)",
+                s
+            )),
+        }
+    }
 }
 
 impl fmt::Display for TextSignFormat {
@@ -111,6 +421,12 @@ impl From<TextSignFormat> for &str {
         match format {
             TextSignFormat::Blake3 => "blake3",
             TextSignFormat::Ed25519 => "ed25519",
+            TextSignFormat::Ed25519ph => "ed25519ph",
+            TextSignFormat::Minisign => "minisign",
+            TextSignFormat::HmacSha256 => "hmac-sha256",
+            TextSignFormat::HmacSha512 => "hmac-sha512",
+            TextSignFormat::EcdsaP256 => "ecdsa-p256",
+            TextSignFormat::EcdsaSecp256k1 => "ecdsa-secp256k1",
         }
     }
 }
@@ -122,11 +438,83 @@ impl FromStr for TextSignFormat {
         match s {
             "blake3" => Ok(TextSignFormat::Blake3),
             "ed25519" => Ok(TextSignFormat::Ed25519),
+            "ed25519ph" => Ok(TextSignFormat::Ed25519ph),
+            "minisign" => Ok(TextSignFormat::Minisign),
+            "hmac-sha256" => Ok(TextSignFormat::HmacSha256),
+            "hmac-sha512" => Ok(TextSignFormat::HmacSha512),
+            "ecdsa-p256" | "p256" | "es256" => Ok(TextSignFormat::EcdsaP256),
+            "ecdsa-secp256k1" | "secp256k1" | "es256k" => Ok(TextSignFormat::EcdsaSecp256k1),
             _ => Err(anyhow::anyhow!("Invalid format")),
         }
     }
 }
 
+impl fmt::Display for KeyEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<KeyEncoding> for &str {
+    fn from(encoding: KeyEncoding) -> Self {
+        match encoding {
+            KeyEncoding::Hex => "hex",
+            KeyEncoding::Base64 => "base64",
+            KeyEncoding::Raw => "raw",
+        }
+    }
+}
+
+impl FromStr for KeyEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(KeyEncoding::Hex),
+            "base64" => Ok(KeyEncoding::Base64),
+            "raw" => Ok(KeyEncoding::Raw),
+            _ => Err(anyhow::anyhow!("Invalid key encoding")),
+        }
+    }
+}
+
+impl fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<KeyFormat> for &str {
+    fn from(format: KeyFormat) -> Self {
+        match format {
+            KeyFormat::Raw => "raw",
+            KeyFormat::Pem => "pem",
+            KeyFormat::OpenSsh => "openssh",
+            KeyFormat::Jwk => "jwk",
+        }
+    }
+}
+
+impl FromStr for KeyFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(KeyFormat::Raw),
+            "pem" | "pkcs8" => Ok(KeyFormat::Pem),
+            "openssh" | "ssh" => Ok(KeyFormat::OpenSsh),
+            "jwk" => Ok(KeyFormat::Jwk),
+            _ => Err(anyhow::anyhow!("Invalid key format")),
+        }
+    }
+}
+
+impl From<PassphraseOpts> for SecretSource {
+    fn from(opts: PassphraseOpts) -> Self {
+        SecretSource::from_options(opts.key, opts.key_file, opts.key_env, opts.key_fd)
+    }
+}
+
 fn parse_format(format: &str) -> Result<TextSignFormat, anyhow::Error> {
     format.parse()
 }
@@ -134,7 +522,56 @@ fn parse_format(format: &str) -> Result<TextSignFormat, anyhow::Error> {
 impl CmdExecutor for TextSignOpts {
     async fn execute(self) -> anyhow::Result<()> {
         let mut reader = get_reader(&self.input)?;
-        let key = get_input_bytes(&self.key)?;
+        let key = load_key(
+            self.key.as_deref(),
+            self.key_id.as_deref(),
+            Some(self.format),
+            true,
+        )?;
+        if self.format == TextSignFormat::Minisign {
+            ensure!(
+                !self.detached,
+                "minisign signatures are already detached; drop --detached"
+            );
+            let file_name = Path::new(&self.input)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.input.clone());
+            let minisig = process_text_sign_minisign(
+                &mut *reader,
+                &key,
+                self.comment.as_deref(),
+                &file_name,
+                !self.legacy,
+            )?;
+            match self.output {
+                Some(path) => fs::write(&path, minisig).await?,
+                None if self.input != "-" => {
+                    fs::write(format!("{}.minisig", self.input), minisig).await?
+                }
+                None => print!("{}", minisig),
+            }
+            return Ok(());
+        }
+        ensure!(
+            self.output.is_none() || self.detached,
+            "--output requires --detached or --format minisign"
+        );
+        if self.detached {
+            let path = match self.output {
+                Some(path) => path,
+                None if self.input != "-" => format!("{}.sig", self.input),
+                None => bail!("--output is required when signing stdin with --detached"),
+            };
+            let signature = process_text_sign_detached(&mut *reader, &key, self.format)?;
+            let json = serde_json::to_string_pretty(&signature)?;
+            fs::write(&path, json + "\n").await?;
+            match &signature.key_id {
+                Some(key_id) => eprintln!("Signature written to {} (key {})", path, key_id),
+                None => eprintln!("Signature written to {}", path),
+            }
+            return Ok(());
+        }
         let signature = process_text_sign(&mut *reader, &key, self.format)?;
         let encoded = URL_SAFE_NO_PAD.encode(signature);
         println!("{}", encoded);
@@ -145,9 +582,48 @@ impl CmdExecutor for TextSignOpts {
 impl CmdExecutor for TextVerifyOpts {
     async fn execute(self) -> anyhow::Result<()> {
         let mut reader = get_reader(&self.input)?;
-        let key = get_input_bytes(&self.key)?;
-        let sig = URL_SAFE_NO_PAD.decode(self.sig.trim())?;
-        let verified = process_text_verify(&mut *reader, &key, &sig, self.format)?;
+        let key = load_key(
+            self.key.as_deref(),
+            self.key_id.as_deref(),
+            self.format,
+            false,
+        )?;
+        let minisign = self.format == Some(TextSignFormat::Minisign)
+            || self
+                .sig_file
+                .as_deref()
+                .is_some_and(|path| path.ends_with(".minisig"));
+        let verified = if minisign {
+            let path = match self.sig_file {
+                Some(path) => path,
+                None if self.input != "-" => format!("{}.minisig", self.input),
+                None => bail!("--sig-file is required when verifying stdin"),
+            };
+            let minisig = fs::read_to_string(&path)
+                .await
+                .with_context(|| format!("Failed to read signature file '{}'", path))?;
+            let trusted_comment = process_text_verify_minisign(&mut *reader, &key, &minisig)?;
+            if let Some(comment) = &trusted_comment {
+                eprintln!("Trusted comment: {}", comment);
+            }
+            trusted_comment.is_some()
+        } else if let Some(sig) = &self.sig {
+            let sig = URL_SAFE_NO_PAD.decode(sig.trim())?;
+            let format = self.format.unwrap_or(TextSignFormat::Blake3);
+            process_text_verify(&mut *reader, &key, &sig, format)?
+        } else {
+            let path = match self.sig_file {
+                Some(path) => path,
+                None if self.input != "-" => format!("{}.sig", self.input),
+                None => bail!("--sig or --sig-file is required when verifying stdin"),
+            };
+            let content = fs::read_to_string(&path)
+                .await
+                .with_context(|| format!("Failed to read signature file '{}'", path))?;
+            let signature: DetachedSignature = serde_json::from_str(&content)
+                .with_context(|| format!("Invalid signature file '{}'", path))?;
+            process_text_verify_detached(&mut *reader, &key, &signature, self.format)?
+        };
         println!("{}", verified);
         Ok(())
     }
@@ -155,41 +631,215 @@ impl CmdExecutor for TextVerifyOpts {
 
 impl CmdExecutor for TextKeyGenerateOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let keys = process_text_key_generate(self.format)?;
+        ensure!(
+            !(self.protect && self.format == TextSignFormat::Minisign),
+            "--protect would break minisign compatibility; use minisign's own key encryption"
+        );
+        let mut keys = process_text_key_generate(self.format, self.key_format, self.encoding)?;
+        let key_pair = key_pair_files(self.format, self.key_format);
+        let fingerprint = match (self.format, key_pair) {
+            (TextSignFormat::Minisign, _) => None,
+            (_, Some((_, pk_name))) => keys
+                .get(pk_name)
+                .map(|key| process_text_key_fingerprint(key, self.format, false))
+                .transpose()?,
+            (_, None) => keys
+                .values()
+                .next()
+                .map(|key| process_text_key_fingerprint(key, self.format, true))
+                .transpose()?,
+        };
+        if self.protect {
+            let passphrase = read_passphrase(PASSPHRASE_ENV, "Enter new passphrase: ", true)?;
+            for (name, key) in keys.iter_mut() {
+                if is_secret_key_file(name) {
+                    *key = protect_key(key, &passphrase)?;
+                }
+            }
+        }
         match self.format {
             TextSignFormat::Blake3 => {
                 let key = keys
                     .get("blake3.txt")
                     .context("Blake3 generator did not return key material")?;
-                fs::write(self.output.join("blake3.txt"), key).await?;
+                write_secret_file(self.output.join("blake3.txt"), key)?;
                 eprintln!("Blake3 key generated successfully");
             }
-            TextSignFormat::Ed25519 => {
+            TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512 => {
+                let key = keys
+                    .get("hmac.txt")
+                    .context("HMAC generator did not return key material")?;
+                write_secret_file(self.output.join("hmac.txt"), key)?;
+                eprintln!("HMAC key generated successfully");
+            }
+            TextSignFormat::Ed25519
+            | TextSignFormat::Ed25519ph
+            | TextSignFormat::EcdsaP256
+            | TextSignFormat::EcdsaSecp256k1 => {
+                let (sk_name, pk_name) = key_pair.context("Not an asymmetric signature format")?;
+                let algorithm = match self.format.curve() {
+                    Some(curve) => format!("ECDSA {}", curve.name()),
+                    None => "Ed25519".to_string(),
+                };
                 ensure!(
-                    keys.contains_key("ed25519.sk") && keys.contains_key("ed25519.pk"),
-                    "Ed25519 generator did not return key pair"
+                    keys.contains_key(sk_name) && keys.contains_key(pk_name),
+                    "{} generator did not return key pair",
+                    algorithm
+                );
+                write_secret_file(self.output.join(sk_name), keys.get(sk_name).unwrap())?;
+                fs::write(self.output.join(pk_name), keys.get(pk_name).unwrap()).await?;
+                eprintln!(
+                    "{} key pair generated successfully ({})",
+                    algorithm, self.key_format
                 );
-                fs::write(
-                    self.output.join("ed25519.sk"),
-                    keys.get("ed25519.sk").unwrap(),
-                )
-                .await?;
-                fs::write(
-                    self.output.join("ed25519.pk"),
-                    keys.get("ed25519.pk").unwrap(),
-                )
-                .await?;
-                eprintln!("Ed25519 key pair generated successfully");
+            }
+            TextSignFormat::Minisign => {
+                let (Some(sk), Some(pk)) = (keys.get("minisign.key"), keys.get("minisign.pub"))
+                else {
+                    bail!("minisign generator did not return key pair");
+                };
+                write_secret_file(self.output.join("minisign.key"), sk)?;
+                fs::write(self.output.join("minisign.pub"), pk).await?;
+                eprintln!("minisign key pair generated successfully (unencrypted)");
             }
         }
+        if let Some(fingerprint) = fingerprint {
+            eprintln!("Fingerprint: {}", fingerprint);
+        }
         Ok(())
     }
 }
 
+impl CmdExecutor for TextKeyConvertOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = unlock_key(get_input_bytes(&self.input)?)?;
+        let raw_is_public = self.input.ends_with(".pk") || self.input.ends_with(".pub");
+        let converted =
+            process_text_key_convert(&key, self.to, raw_is_public, self.public, &self.comment)?;
+        match self.output.as_deref() {
+            Some(path) if path != "-" && !self.public => write_secret_file(path, &converted)?,
+            output => {
+                let mut writer = get_writer(output)?;
+                writer.write_all(&converted)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CmdExecutor for TextKeyAgeKeygenOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let (identity, public) = process_text_age_keygen();
+        match self.output.as_deref() {
+            Some(path) if path != "-" => write_secret_file(path, identity.as_bytes())?,
+            _ => print!("{}", identity),
+        }
+        eprintln!("Public key: {}", public);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for TextKeyPasswdOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let data = fs::read(&self.input).await?;
+        let key = unlock_key(data)?;
+        let updated = if self.remove {
+            key
+        } else {
+            let passphrase = read_passphrase(NEW_PASSPHRASE_ENV, "Enter new passphrase: ", true)?;
+            Zeroizing::new(protect_key(&key, &passphrase)?)
+        };
+        write_secret_file(&self.input, &updated)?;
+        if self.remove {
+            eprintln!("Passphrase removed from {}", self.input);
+        } else {
+            eprintln!("Passphrase updated for {}", self.input);
+        }
+        Ok(())
+    }
+}
+
+/// 从 `--key` 文件或密钥环（`--key-id`）加载密钥，受口令保护时自动解锁
+fn load_key(
+    key: Option<&str>,
+    key_id: Option<&str>,
+    format: Option<TextSignFormat>,
+    secret: bool,
+) -> anyhow::Result<Zeroizing<Vec<u8>>> {
+    if let Some(path) = key {
+        return unlock_key(get_input_bytes(path)?);
+    }
+    let query = key_id.context("Either --key or --key-id is required")?;
+    let keyring = Keyring::open()?;
+    let entry = keyring.find(query, Some(secret))?;
+    if let Some(format) = format {
+        ensure!(
+            entry.supports(format),
+            "Key '{}' is a {} key and cannot be used with {}",
+            entry.name,
+            entry.algorithm,
+            format
+        );
+    }
+    ensure!(
+        entry.secret || !secret,
+        "Key '{}' is a public key; signing requires a secret key",
+        entry.name
+    );
+    let data = unlock_key(keyring.load(&entry)?)?;
+    if !secret && entry.secret && entry.algorithm == "ed25519" {
+        // 原始私钥字节不能直接当作公钥解析，先导出对应公钥
+        return Ok(Zeroizing::new(process_text_key_convert(
+            &data,
+            KeyFormat::Raw,
+            false,
+            true,
+            "",
+        )?));
+    }
+    Ok(data)
+}
+
+/// `--raw-key` 文件优先，否则使用 `--key` 口令（clap 保证两者必有其一）
+fn encryption_key(
+    passphrase: PassphraseOpts,
+    raw_key: Option<&str>,
+    confirm: bool,
+) -> anyhow::Result<EncryptionKey> {
+    match raw_key {
+        // 与签名密钥一样，`--protect` 生成的原始密钥文件先解锁
+        Some(path) => {
+            EncryptionKey::raw_from_bytes(&Zeroizing::new(unlock_key(get_input_bytes(path)?)?))
+        }
+        None => {
+            let passphrase = SecretSource::from(passphrase).read("Enter passphrase: ", confirm)?;
+            Ok(EncryptionKey::Passphrase(
+                passphrase.expose_secret().as_bytes().to_vec(),
+            ))
+        }
+    }
+}
+
+/// `--aad` 或 `--aad-file` 提供的关联数据，二者都未指定时为 `None`
+fn associated_data(aad: Option<String>, aad_file: Option<&str>) -> anyhow::Result<Option<Vec<u8>>> {
+    match aad_file {
+        Some(path) => Ok(Some(get_input_bytes(path)?)),
+        None => Ok(aad.map(String::into_bytes)),
+    }
+}
+
+/// 生成结果中哪些文件是私钥（需要口令保护）
+fn is_secret_key_file(name: &str) -> bool {
+    !(name.ends_with(".pk") || name.ends_with(".pub") || name.contains(".pub."))
+}
+
 impl CmdExecutor for TextEncryptOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let mut reader: Box<dyn Read> = if let Some(text) = self.text {
-            Box::new(Cursor::new(text.into_bytes()))
+        let mut reader: Box<dyn Read> = if let Some(text) = &self.text {
+            Box::new(Cursor::new(Zeroizing::new(
+                text.expose_secret().as_bytes().to_vec(),
+            )))
         } else {
             if self.input == "-" {
                 eprintln!(
@@ -198,7 +848,45 @@ impl CmdExecutor for TextEncryptOpts {
             }
             get_reader(&self.input)?
         };
-        let ciphertext = process_text_encrypt(&mut *reader, self.key.as_bytes())?;
+        if !self.recipient.is_empty() || !self.recipients_file.is_empty() {
+            let mut recipients = self
+                .recipient
+                .iter()
+                .map(|recipient| parse_recipient(recipient))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            for path in &self.recipients_file {
+                recipients.extend(parse_recipients_file(&get_input_bytes(path)?)?);
+            }
+            let armor = self.armor || self.output.is_none();
+            let mut writer = get_writer(self.output.as_deref())?;
+            process_text_age_encrypt(&mut *reader, &mut writer, &recipients, armor)?;
+            writer.flush()?;
+            return Ok(());
+        }
+
+        let key = encryption_key(self.passphrase, self.raw_key.as_deref(), true)?;
+        let aad = associated_data(self.aad, self.aad_file.as_deref())?;
+        let params = KdfParams {
+            memory: self.kdf_memory,
+            iterations: self.kdf_iterations,
+            parallelism: self.kdf_parallelism,
+        };
+        if let Some(output) = self.output {
+            let mut writer = BufWriter::new(std::fs::File::create(&output)?);
+            process_text_encrypt_stream(
+                &mut *reader,
+                &mut writer,
+                &key,
+                self.cipher,
+                &params,
+                aad.as_deref(),
+            )?;
+            writer.flush()?;
+            eprintln!("Encrypted to {}", output);
+            return Ok(());
+        }
+        let ciphertext =
+            process_text_encrypt(&mut *reader, &key, self.cipher, &params, aad.as_deref())?;
         let encoded = STANDARD_NO_PAD.encode(ciphertext);
         println!("{}", encoded);
         Ok(())
@@ -207,22 +895,71 @@ impl CmdExecutor for TextEncryptOpts {
 
 impl CmdExecutor for TextDecryptOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let data = if let Some(cipher) = self.cipher {
-            cipher
+        let mut identities = Vec::new();
+        for path in &self.identity {
+            identities.extend(parse_identities(&get_input_bytes(path)?)?);
+        }
+        let key = if identities.is_empty() {
+            Some(encryption_key(
+                self.passphrase,
+                self.raw_key.as_deref(),
+                false,
+            )?)
+        } else {
+            None
+        };
+        let aad = associated_data(self.aad, self.aad_file.as_deref())?;
+        if let Some(output) = self.output {
+            let mut reader = get_reader(&self.input)?;
+            let mut writer = BufWriter::new(std::fs::File::create(&output)?);
+            let result = match &key {
+                Some(key) => process_text_decrypt_stream(
+                    &mut *reader,
+                    &mut writer,
+                    key,
+                    self.cipher,
+                    aad.as_deref(),
+                ),
+                None => process_text_age_decrypt(&mut *reader, &mut writer, &identities),
+            }
+            .and_then(|_| Ok(writer.flush()?));
+            if result.is_err() {
+                // 不保留未通过认证的部分明文
+                drop(writer);
+                let _ = std::fs::remove_file(&output);
+            }
+            return result;
+        }
+
+        let data = if let Some(ciphertext) = self.ciphertext {
+            ciphertext.into_bytes()
         } else {
             if self.input == "-" {
                 eprintln!("请输入 Base64 编码的密文，结束后按 Ctrl+D (macOS/Linux) 或 Ctrl+Z (Windows) 提交。");
             }
-            let mut reader = get_reader(&self.input)?;
-            let mut data = String::new();
-            reader.read_to_string(&mut data)?;
-            data
+            get_input_bytes(&self.input)?
+        };
+        let plaintext = match key {
+            Some(key) => {
+                ensure!(
+                    !is_age_file(&data),
+                    "Input is an age file; decrypt it with --identity"
+                );
+                // `--output` 生成的二进制容器以 RSTE 魔数开头，其余输入按 Base64 处理
+                let decoded = if data.starts_with(b"RSTE") {
+                    data
+                } else {
+                    STANDARD_NO_PAD.decode(data.trim_ascii())?
+                };
+                process_text_decrypt(&decoded, &key, self.cipher, aad.as_deref())?
+            }
+            None => {
+                let mut plaintext = Zeroizing::new(Vec::new());
+                process_text_age_decrypt(&mut &data[..], &mut *plaintext, &identities)?;
+                plaintext
+            }
         };
-        let data = data.trim();
-        let decoded = STANDARD_NO_PAD.decode(data)?;
-        let plaintext = process_text_decrypt(&decoded, self.key.as_bytes())?;
-        use std::io::Write;
-        match String::from_utf8(plaintext.clone()) {
+        match std::str::from_utf8(&plaintext) {
             Ok(text) => println!("{}", text),
             Err(_) => {
                 // 输出原始字节供用户自行处理
@@ -232,3 +969,66 @@ impl CmdExecutor for TextDecryptOpts {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::key_protect::protect_key_with;
+    use crate::{process_text_decrypt, process_text_encrypt};
+
+    #[test]
+    fn test_cipher_selects_algorithm() -> anyhow::Result<()> {
+        for flag in ["--cipher", "--algorithm"] {
+            let opts =
+                TextEncryptOpts::try_parse_from(["encrypt", "--key", "k", flag, "aes256gcm"])?;
+            assert_eq!(opts.cipher, TextCipher::Aes256Gcm);
+            let opts = TextDecryptOpts::try_parse_from([
+                "decrypt",
+                "--key",
+                "k",
+                "--ciphertext",
+                "AAAA",
+                flag,
+                "aes256gcm",
+            ])?;
+            assert_eq!(opts.ciphertext.as_deref(), Some("AAAA"));
+            assert_eq!(opts.cipher, Some(TextCipher::Aes256Gcm));
+        }
+
+        // 旧脚本用 --cipher 传密文时应提示改用 --ciphertext
+        let err = TextDecryptOpts::try_parse_from(["decrypt", "--key", "k", "--cipher", "AAAA"])
+            .expect_err("ciphertext is not a cipher name");
+        assert!(err.to_string().contains("--ciphertext"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_protected_raw_key_roundtrip() -> anyhow::Result<()> {
+        let raw = format!("{}\n", hex::encode([9u8; 32]));
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(&protect_key_with(
+            raw.as_bytes(),
+            "raw key pass",
+            1024,
+            1,
+            1,
+        )?)?;
+        let path = file.path().to_string_lossy().to_string();
+        std::env::set_var(PASSPHRASE_ENV, "raw key pass");
+
+        let key = encryption_key(PassphraseOpts::default(), Some(&path), true)?;
+        assert!(matches!(key, EncryptionKey::Raw(bytes) if bytes == [9u8; 32]));
+        let params = KdfParams::default();
+        let sealed = process_text_encrypt(
+            &mut &b"protected raw key"[..],
+            &key,
+            TextCipher::XChaCha20Poly1305,
+            &params,
+            None,
+        )?;
+        let key = encryption_key(PassphraseOpts::default(), Some(&path), false)?;
+        let opened = process_text_decrypt(&sealed, &key, None, None)?;
+        assert_eq!(&opened[..], b"protected raw key");
+        Ok(())
+    }
+}
@@ -3,19 +3,36 @@ mod process;
 mod utils;
 
 pub use crate::cli::{
-    Base64DecodeOpts, Base64EncodeOpts, Base64Format, Base64SubCommand, CsvOpts, GenPassOpts,
-    HttpServeOpts, HttpSubCommand, JwtSignOpts, JwtSubCommand, JwtVerifyOpts, Opts, OutputFormat,
-    SubCommand, TextDecryptOpts, TextEncryptOpts, TextKeyGenerateOpts, TextSignFormat,
-    TextSignOpts, TextSubCommand, TextVerifyOpts,
+    Base64DecodeOpts, Base64EncodeOpts, Base64Format, Base64SubCommand, Codec, CsvOpts, DecodeOpts,
+    EncodeOpts, GenPassDeriveOpts, GenPassOpts, GenPassSubCommand, HashAlgorithm, HashOpts,
+    HttpServeOpts, HttpSubCommand, JwtSecretOpts, JwtSignOpts, JwtSubCommand, JwtVerifyOpts,
+    KeyAddOpts, KeyEncoding, KeyExportOpts, KeyFormat, KeyListOpts, KeyRemoveOpts, KeySubCommand,
+    Opts, OtpAlgorithm, OtpGenerateOpts, OtpMode, OtpSecretNewOpts, OtpSecretSubCommand,
+    OtpSubCommand, OtpVerifyOpts, OutputFormat, PassphraseOpts, SubCommand, TextCipher,
+    TextDecryptOpts, TextEncryptOpts, TextKeyAgeKeygenOpts, TextKeyConvertOpts,
+    TextKeyGenerateOpts, TextKeyPasswdOpts, TextKeySubCommand, TextSignFormat, TextSignOpts,
+    TextSubCommand, TextVerifyOpts,
 };
 use enum_dispatch::enum_dispatch;
 
 pub use crate::process::{
-    process_csv, process_decode, process_encode, process_genpass, process_http_serve,
-    process_jwt_sign, process_jwt_verify, process_text_decrypt, process_text_encrypt,
-    process_text_key_generate, process_text_sign, process_text_verify,
+    ed25519_key_files, is_age_file, is_protected, key_pair_files, parse_identities,
+    parse_recipient, parse_recipients_file, process_codec_decode, process_codec_encode,
+    process_csv, process_decode, process_encode, process_genpass, process_genpass_derive,
+    process_hash, process_hash_check, process_http_serve, process_jwt_sign, process_jwt_verify,
+    process_otp_generate, process_otp_secret_new, process_otp_verify, process_text_age_decrypt,
+    process_text_age_encrypt, process_text_age_keygen, process_text_decrypt,
+    process_text_decrypt_stream, process_text_encrypt, process_text_encrypt_stream,
+    process_text_key_convert, process_text_key_fingerprint, process_text_key_generate,
+    process_text_sign, process_text_sign_detached, process_text_sign_minisign, process_text_verify,
+    process_text_verify_detached, process_text_verify_minisign, protect_key, read_passphrase,
+    unlock_key, unprotect_key, AgeIdentity, AgeRecipient, DetachedSignature, EncryptionKey,
+    KdfParams, KeyEntry, Keyring, SecretSource, KEYRING_DIR_ENV, NEW_PASSPHRASE_ENV,
+    PASSPHRASE_ENV,
+};
+pub use crate::utils::{
+    get_input_bytes, get_input_string, get_reader, get_writer, write_secret_file,
 };
-pub use crate::utils::{get_input_bytes, get_input_string, get_reader};
 
 #[allow(async_fn_in_trait)]
 #[enum_dispatch]
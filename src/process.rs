@@ -1,16 +1,43 @@
 pub mod b64;
+pub mod codec;
+pub mod container;
 pub mod csv_convert;
+pub mod ec_key;
 pub mod gen_pass;
+pub mod hash;
 pub mod http_serve;
 pub mod jwt;
+pub mod key_format;
+pub mod key_protect;
+pub mod keyring;
+pub mod minisign;
+pub mod otp;
+pub mod recipients;
 pub mod text;
 
 pub use b64::{process_decode, process_encode};
+pub use codec::{process_codec_decode, process_codec_encode};
+pub use container::KdfParams;
 pub use csv_convert::process_csv;
-pub use gen_pass::process_genpass;
+pub use gen_pass::{process_genpass, process_genpass_derive};
+pub use hash::{process_hash, process_hash_check};
 pub use http_serve::process_http_serve;
 pub use jwt::{process_jwt_sign, process_jwt_verify};
+pub use key_protect::{
+    is_protected, protect_key, read_passphrase, unlock_key, unprotect_key, SecretSource,
+    NEW_PASSPHRASE_ENV, PASSPHRASE_ENV,
+};
+pub use keyring::{KeyEntry, Keyring, KEYRING_DIR_ENV};
+pub use otp::{process_otp_generate, process_otp_secret_new, process_otp_verify};
+pub use recipients::{
+    is_age_file, parse_identities, parse_recipient, parse_recipients_file,
+    process_text_age_decrypt, process_text_age_encrypt, process_text_age_keygen, AgeIdentity,
+    AgeRecipient,
+};
 pub use text::{
-    process_text_decrypt, process_text_encrypt, process_text_key_generate, process_text_sign,
-    process_text_verify,
+    ed25519_key_files, key_pair_files, process_text_decrypt, process_text_decrypt_stream,
+    process_text_encrypt, process_text_encrypt_stream, process_text_key_convert,
+    process_text_key_fingerprint, process_text_key_generate, process_text_sign,
+    process_text_sign_detached, process_text_sign_minisign, process_text_verify,
+    process_text_verify_detached, process_text_verify_minisign, DetachedSignature, EncryptionKey,
 };
@@ -1,58 +1,665 @@
-use crate::{get_input_bytes, get_input_string, Base64DecodeOpts, Base64EncodeOpts, Base64Format};
-use anyhow::Context;
-use base64::prelude::*;
-use std::io::{self, Write};
+use crate::{get_reader, get_writer, Base64DecodeOpts, Base64EncodeOpts, Base64Format};
+use anyhow::{bail, ensure, Context};
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::read::DecoderReader;
+use base64::write::EncoderWriter;
+use std::io::{self, BufRead, BufReader, Cursor, IsTerminal, Read, Write};
+use std::path::Path;
 
-pub fn process_encode(opts: &Base64EncodeOpts) -> anyhow::Result<String> {
+/// `--detect` 时用于判断字母表的前缀长度
+const DETECT_PREFIX_LEN: u64 = 64 * 1024;
+/// `--data-uri` 嗅探媒体类型时读取的前缀长度
+const SNIFF_PREFIX_LEN: u64 = 512;
+/// PEM 正文每行 64 个字符（RFC 7468）
+const PEM_COLS: usize = 64;
+
+pub fn process_encode(opts: &Base64EncodeOpts) -> anyhow::Result<()> {
     if opts.input == "-" {
         eprintln!("请输入要编码的内容，按 Ctrl+D (Unix/Mac) 或 Ctrl+Z (Windows) 结束输入：");
     }
-    let data =
-        get_input_bytes(&opts.input).with_context(|| format!("无法读取输入源 '{}'", opts.input))?;
+    let mut reader =
+        get_reader(&opts.input).with_context(|| format!("无法读取输入源 '{}'", opts.input))?;
+    let mut writer = get_writer(opts.output.as_deref())?;
+    let engine = engine(opts.format, !opts.no_pad, false);
 
-    if data.is_empty() {
-        eprintln!("警告：输入内容为空，没有数据需要编码。");
-        return Ok(String::new());
+    if opts.data_uri {
+        let (sniffed, prefixed) = sniff_mime(reader, &opts.input)?;
+        reader = prefixed;
+        let mime = opts.mime_type.as_deref().unwrap_or(sniffed);
+        write!(writer, "data:{};base64,", mime)?;
+    }
+    if let Some(label) = &opts.pem {
+        ensure!(
+            !label.is_empty() && label.bytes().all(|b| b.is_ascii_graphic() || b == b' '),
+            "PEM 标签只能包含可打印 ASCII 字符"
+        );
+        writeln!(writer, "-----BEGIN {}-----", label)?;
     }
 
-    let encoded = match opts.format {
-        Base64Format::Standard => BASE64_STANDARD.encode(&data),
-        Base64Format::UrlSafe => BASE64_URL_SAFE.encode(&data),
+    let wrap = if opts.pem.is_some() {
+        Some((PEM_COLS, "\n"))
+    } else if opts.mime {
+        Some((76, "\r\n"))
+    } else {
+        opts.wrap.map(|cols| (cols, "\n"))
+    };
+    let written = match wrap {
+        Some((cols, eol)) => {
+            ensure!(cols > 0, "--wrap 必须大于 0");
+            let mut wrapped = LineWrapper::new(&mut writer, cols, eol);
+            encode_stream(&mut reader, &mut wrapped, &engine)?
+        }
+        None => encode_stream(&mut reader, &mut writer, &engine)?,
     };
+    let armored = opts.data_uri || opts.pem.is_some();
+    if written == 0 {
+        eprintln!("警告：输入内容为空，没有数据需要编码。");
+        if !armored {
+            return Ok(());
+        }
+    }
 
-    println!("{}", encoded);
-    Ok(encoded)
+    if let Some(label) = &opts.pem {
+        if written > 0 {
+            writeln!(writer)?;
+        }
+        write!(writer, "-----END {}-----", label)?;
+    }
+    if !opts.raw {
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
 }
 
-pub fn process_decode(opts: &Base64DecodeOpts) -> anyhow::Result<Vec<u8>> {
+pub fn process_decode(opts: &Base64DecodeOpts) -> anyhow::Result<()> {
     if opts.input == "-" {
         eprintln!("请输入要编码的内容，按 Ctrl+D (Unix/Mac) 或 Ctrl+Z (Windows) 结束输入：");
     }
-    let data = get_input_string(&opts.input)
-        .with_context(|| format!("无法读取输入源 '{}'", opts.input))?;
+    let reader =
+        get_reader(&opts.input).with_context(|| format!("无法读取输入源 '{}'", opts.input))?;
+    // data: URI 与 PEM 外壳总是自动剥离
+    let mut reader = unwrap_armor(reader)?;
+    let lenient = opts.lenient || opts.detect;
+    let format = if opts.detect {
+        let (format, prefixed) = detect_format(reader, opts.format)?;
+        eprintln!("检测到 Base64 格式：{}", format);
+        reader = prefixed;
+        format
+    } else {
+        opts.format
+    };
 
-    let trimmed_data = data.trim();
-    if trimmed_data.is_empty() {
+    let terminal = matches!(opts.output.as_deref(), None | Some("-")) && io::stdout().is_terminal();
+    let mut writer = Utf8Tracker::new(get_writer(opts.output.as_deref())?, terminal && !opts.force);
+
+    let engine = engine(format, true, lenient);
+    let written = decode_stream(&mut reader, &mut writer, &engine, lenient)?;
+    if written == 0 {
         eprintln!("警告：输入内容为空，没有数据需要解码。");
-        return Ok(Vec::new());
+        return Ok(());
     }
 
-    let decoded = match opts.format {
-        Base64Format::Standard => BASE64_STANDARD.decode(trimmed_data),
-        Base64Format::UrlSafe => BASE64_URL_SAFE.decode(trimmed_data),
+    // 仅当输出到终端且结果为合法 UTF-8 文本时补一个换行，管道、文件或 --raw 时保持原样
+    if terminal && !opts.raw && writer.is_utf8() {
+        writeln!(writer)?;
     }
-    .context("Base64 解码失败，请检查输入格式是否正确")?;
+    writer.flush()?;
+    Ok(())
+}
 
-    // 尝试以 UTF-8 字符串输出，如果失败则输出原始字节
-    match String::from_utf8(decoded.clone()) {
-        Ok(text) => {
-            println!("{}", text);
-            io::stdout().flush()?;
+/// 按格式与选项构造引擎；宽松模式下解码同时接受带/不带填充的输入
+fn engine(format: Base64Format, pad: bool, lenient: bool) -> GeneralPurpose {
+    let alphabet = match format {
+        Base64Format::Standard => &alphabet::STANDARD,
+        Base64Format::UrlSafe => &alphabet::URL_SAFE,
+    };
+    let padding = if lenient {
+        DecodePaddingMode::Indifferent
+    } else {
+        DecodePaddingMode::RequireCanonical
+    };
+    let config = GeneralPurposeConfig::new()
+        .with_encode_padding(pad)
+        .with_decode_padding_mode(padding);
+    GeneralPurpose::new(alphabet, config)
+}
+
+/// 读取输入前缀判断字母表：出现 `-`/`_` 为 URL 安全，出现 `+`/`/` 为标准；
+/// 无法区分时沿用 `fallback`。读出的前缀会重新拼接回输入流。
+fn detect_format(
+    mut reader: Box<dyn Read>,
+    fallback: Base64Format,
+) -> anyhow::Result<(Base64Format, Box<dyn Read>)> {
+    let mut prefix = Vec::new();
+    reader
+        .by_ref()
+        .take(DETECT_PREFIX_LEN)
+        .read_to_end(&mut prefix)?;
+
+    let url_safe = prefix.iter().any(|b| matches!(b, b'-' | b'_'));
+    let standard = prefix.iter().any(|b| matches!(b, b'+' | b'/'));
+    let format = match (standard, url_safe) {
+        (true, true) => bail!("输入同时包含标准与 URL 安全字母表的字符，无法识别"),
+        (true, false) => Base64Format::Standard,
+        (false, true) => Base64Format::UrlSafe,
+        (false, false) => fallback,
+    };
+    Ok((format, Box::new(Cursor::new(prefix).chain(reader))))
+}
+
+/// 根据内容魔数嗅探媒体类型，失败时再按文件扩展名推断；读出的前缀会重新拼接回输入流
+fn sniff_mime(
+    mut reader: Box<dyn Read>,
+    input: &str,
+) -> anyhow::Result<(&'static str, Box<dyn Read>)> {
+    let mut prefix = Vec::new();
+    reader
+        .by_ref()
+        .take(SNIFF_PREFIX_LEN)
+        .read_to_end(&mut prefix)?;
+    let mime = mime_from_magic(&prefix)
+        .or_else(|| mime_from_extension(input))
+        .unwrap_or_else(|| {
+            // 前缀末尾可能截断多字节字符，只要求截断前的部分是合法 UTF-8
+            let text = match std::str::from_utf8(&prefix) {
+                Ok(_) => true,
+                Err(e) => e.error_len().is_none(),
+            };
+            let control = prefix
+                .iter()
+                .any(|&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r'));
+            if text && !control {
+                "text/plain;charset=utf-8"
+            } else {
+                "application/octet-stream"
+            }
+        });
+    Ok((mime, Box::new(Cursor::new(prefix).chain(reader))))
+}
+
+fn mime_from_magic(data: &[u8]) -> Option<&'static str> {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\0asm", "application/wasm"),
+        (b"\0\0\x01\0", "image/x-icon"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+        (b"ID3", "audio/mpeg"),
+    ];
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| data.starts_with(magic)) {
+        return Some(mime);
+    }
+    if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    let head = String::from_utf8_lossy(data).to_ascii_lowercase();
+    let head = head.trim_start();
+    if head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg")) {
+        return Some("image/svg+xml");
+    }
+    if head.starts_with("<!doctype html") || head.starts_with("<html") {
+        return Some("text/html");
+    }
+    None
+}
+
+fn mime_from_extension(input: &str) -> Option<&'static str> {
+    let ext = Path::new(input).extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "wasm" => "application/wasm",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// 若输入是 `data:...;base64,` URI 或 PEM 块，剥离外壳只保留 Base64 正文
+fn unwrap_armor(reader: Box<dyn Read>) -> anyhow::Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(reader);
+    loop {
+        let buf = reader.fill_buf()?;
+        let spaces = buf.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        if spaces == 0 {
+            break;
+        }
+        reader.consume(spaces);
+    }
+
+    let head = reader.fill_buf()?;
+    if head.starts_with(b"data:") {
+        let mut header = Vec::new();
+        reader.read_until(b',', &mut header)?;
+        let header = String::from_utf8_lossy(&header);
+        ensure!(header.ends_with(','), "data: URI 缺少 ',' 分隔符");
+        ensure!(
+            header.trim_end_matches(',').ends_with(";base64"),
+            "仅支持 Base64 编码的 data: URI"
+        );
+        eprintln!("检测到 data URI：{}", header.trim_end_matches(','));
+        return Ok(Box::new(reader));
+    }
+    if head.starts_with(b"-----BEGIN ") {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        eprintln!("检测到 PEM 块：{}", line.trim());
+        return Ok(Box::new(PemBody {
+            inner: reader,
+            line: Vec::new(),
+            pos: 0,
+            done: false,
+        }));
+    }
+    Ok(Box::new(reader))
+}
+
+/// 逐行读取 PEM 正文：跳过 `Key: value` 形式的头部行，遇到 `-----END` 即结束
+struct PemBody<R> {
+    inner: R,
+    line: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Read for PemBody<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.line.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.line.clear();
+            self.pos = 0;
+            if self.inner.read_until(b'\n', &mut self.line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "PEM 块缺少 -----END 行",
+                ));
+            }
+            if self.line.starts_with(b"-----END") {
+                self.done = true;
+                self.line.clear();
+            } else if self.line.contains(&b':') {
+                self.line.clear();
+            }
         }
-        Err(_) => {
-            io::stdout().write_all(&decoded)?;
+        let n = buf.len().min(self.line.len() - self.pos);
+        buf[..n].copy_from_slice(&self.line[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// 以固定大小的缓冲区流式编码，返回读取的原始字节数
+fn encode_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    engine: &GeneralPurpose,
+) -> anyhow::Result<u64> {
+    let mut encoder = EncoderWriter::new(writer, engine);
+    let read = io::copy(reader, &mut encoder)?;
+    encoder.finish()?;
+    Ok(read)
+}
+
+/// 流式解码，返回写出的字节数；默认忽略换行符，宽松模式下忽略所有空白字符
+fn decode_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    engine: &GeneralPurpose,
+    lenient: bool,
+) -> anyhow::Result<u64> {
+    let filtered = SkipWhitespace {
+        inner: reader,
+        all: lenient,
+    };
+    let mut decoder = DecoderReader::new(filtered, engine);
+    io::copy(&mut decoder, writer).context("Base64 解码失败，请检查输入格式是否正确")
+}
+
+/// 过滤掉输入中的换行符（`all` 为 true 时过滤所有 ASCII 空白），使折行的 Base64 也能被解码
+struct SkipWhitespace<R> {
+    inner: R,
+    all: bool,
+}
+
+impl<R: Read> Read for SkipWhitespace<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut kept = 0;
+            for i in 0..n {
+                let skip = if self.all {
+                    buf[i].is_ascii_whitespace()
+                } else {
+                    buf[i] == b'\n' || buf[i] == b'\r'
+                };
+                if !skip {
+                    buf[kept] = buf[i];
+                    kept += 1;
+                }
+            }
+            if kept > 0 {
+                return Ok(kept);
+            }
         }
     }
+}
 
-    Ok(decoded)
+/// 按固定列宽插入换行（MIME 使用 76 列 + CRLF），末行之后不追加换行
+struct LineWrapper<W> {
+    inner: W,
+    cols: usize,
+    eol: &'static str,
+    column: usize,
+}
+
+impl<W: Write> LineWrapper<W> {
+    fn new(inner: W, cols: usize, eol: &'static str) -> Self {
+        Self {
+            inner,
+            cols,
+            eol,
+            column: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for LineWrapper<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.column == self.cols {
+                self.inner.write_all(self.eol.as_bytes())?;
+                self.column = 0;
+            }
+            let take = rest.len().min(self.cols - self.column);
+            self.inner.write_all(&rest[..take])?;
+            self.column += take;
+            rest = &rest[take..];
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 在透传写入的同时增量校验 UTF-8，跨块的多字节字符会暂存到下一次写入
+///
+/// `refuse_binary` 为 true 时，一旦发现二进制内容（非法 UTF-8 或 NUL）即拒绝写入，
+/// 避免把二进制数据直接倾倒到终端。
+pub(crate) struct Utf8Tracker<W> {
+    inner: W,
+    pending: Vec<u8>,
+    valid: bool,
+    refuse_binary: bool,
+}
+
+impl<W: Write> Utf8Tracker<W> {
+    pub(crate) fn new(inner: W, refuse_binary: bool) -> Self {
+        Self {
+            inner,
+            pending: Vec::new(),
+            valid: true,
+            refuse_binary,
+        }
+    }
+
+    pub(crate) fn is_utf8(&self) -> bool {
+        self.valid && self.pending.is_empty()
+    }
+}
+
+impl<W: Write> Write for Utf8Tracker<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.valid {
+            self.pending.extend_from_slice(buf);
+            match std::str::from_utf8(&self.pending) {
+                Ok(_) => self.pending.clear(),
+                // error_len() 为 None 表示末尾只是不完整的字符，等待后续数据
+                Err(e) if e.error_len().is_none() => {
+                    self.pending.drain(..e.valid_up_to());
+                }
+                Err(_) => {
+                    self.valid = false;
+                    self.pending.clear();
+                }
+            }
+            if buf.contains(&0) {
+                self.valid = false;
+            }
+        }
+        if !self.valid && self.refuse_binary {
+            return Err(io::Error::other(
+                "解码结果为二进制数据，拒绝输出到终端；请使用 --output 写入文件或加 --force 强制输出",
+            ));
+        }
+        self.inner.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use base64::Engine;
+
+    #[test]
+    fn test_encode_decode_stream_roundtrip() -> anyhow::Result<()> {
+        // 大于 io::copy 的缓冲区，确保跨块处理正确
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        for format in [Base64Format::Standard, Base64Format::UrlSafe] {
+            let engine = engine(format, true, false);
+            let mut encoded = Vec::new();
+            let read = encode_stream(&mut &data[..], &mut encoded, &engine)?;
+            assert_eq!(read, data.len() as u64);
+            assert_eq!(encoded, engine.encode(&data).into_bytes());
+
+            let mut decoded = Vec::new();
+            decode_stream(&mut &encoded[..], &mut decoded, &engine, false)?;
+            assert_eq!(decoded, data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_stream_skips_line_breaks() -> anyhow::Result<()> {
+        let mut decoded = Vec::new();
+        decode_stream(
+            &mut &b"aGVsbG8g\r\nd29y\nbGQ=\n"[..],
+            &mut decoded,
+            &engine(Base64Format::Standard, true, false),
+            false,
+        )?;
+        assert_eq!(decoded, b"hello world");
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_stream_invalid_input() {
+        let mut decoded = Vec::new();
+        let result = decode_stream(
+            &mut &b"not base64!"[..],
+            &mut decoded,
+            &engine(Base64Format::Standard, true, false),
+            false,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_no_pad_and_lenient_decode() -> anyhow::Result<()> {
+        let mut encoded = Vec::new();
+        encode_stream(
+            &mut &b"hello"[..],
+            &mut encoded,
+            &engine(Base64Format::UrlSafe, false, false),
+        )?;
+        assert_eq!(encoded, b"aGVsbG8");
+
+        // 严格模式拒绝缺少填充的输入，宽松模式同时接受两种写法并忽略空白
+        let strict = engine(Base64Format::UrlSafe, true, false);
+        assert!(decode_stream(&mut &encoded[..], &mut Vec::new(), &strict, false).is_err());
+        let lenient = engine(Base64Format::UrlSafe, true, true);
+        for input in [&b"aGVs bG8"[..], b"aGVs\tbG8=\n"] {
+            let mut decoded = Vec::new();
+            decode_stream(&mut &input[..], &mut decoded, &lenient, true)?;
+            assert_eq!(decoded, b"hello");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_wrapper() -> io::Result<()> {
+        let mut out = Vec::new();
+        let mut wrapper = LineWrapper::new(&mut out, 4, "\r\n");
+        wrapper.write_all(b"abcdef")?;
+        wrapper.write_all(b"gh")?;
+        assert_eq!(out, b"abcd\r\nefgh");
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_format() -> anyhow::Result<()> {
+        let cases = [
+            (&b"-_8"[..], Base64Format::UrlSafe),
+            (b"+/8=", Base64Format::Standard),
+            (b"aGVsbG8", Base64Format::Standard),
+        ];
+        for (input, expected) in cases {
+            let (format, mut reader) = detect_format(Box::new(input), Base64Format::Standard)?;
+            assert_eq!(format, expected);
+            let mut replayed = Vec::new();
+            reader.read_to_end(&mut replayed)?;
+            assert_eq!(replayed, input);
+        }
+        assert!(detect_format(Box::new(&b"+-"[..]), Base64Format::Standard).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sniff_mime() -> anyhow::Result<()> {
+        let png = b"\x89PNG\r\n\x1a\n rest of image".to_vec();
+        let (mime, mut reader) = sniff_mime(Box::new(Cursor::new(png.clone())), "-")?;
+        assert_eq!(mime, "image/png");
+        let mut replayed = Vec::new();
+        reader.read_to_end(&mut replayed)?;
+        assert_eq!(replayed, png);
+
+        let (mime, _) = sniff_mime(Box::new(&b"body { color: red }"[..]), "site.css")?;
+        assert_eq!(mime, "text/css");
+        let (mime, _) = sniff_mime(Box::new(&b"hello"[..]), "-")?;
+        assert_eq!(mime, "text/plain;charset=utf-8");
+        let (mime, _) = sniff_mime(Box::new(&[0u8, 1, 2][..]), "-")?;
+        assert_eq!(mime, "application/octet-stream");
+        Ok(())
+    }
+
+    #[test]
+    fn test_unwrap_armor() -> anyhow::Result<()> {
+        let inputs = [
+            &b"data:text/plain;base64,aGVsbG8="[..],
+            b"\n-----BEGIN DATA-----\nProc-Type: 4,ENCRYPTED\n\naGVs\nbG8=\n-----END DATA-----\ntrailing",
+            b"aGVsbG8=\n",
+        ];
+        let engine = engine(Base64Format::Standard, true, false);
+        for input in inputs {
+            let mut reader = unwrap_armor(Box::new(input))?;
+            let mut decoded = Vec::new();
+            decode_stream(&mut reader, &mut decoded, &engine, false)?;
+            assert_eq!(decoded, b"hello");
+        }
+
+        assert!(unwrap_armor(Box::new(&b"data:text/plain,hello"[..])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_pem_and_data_uri() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("blob.bin");
+        std::fs::write(&input, [7u8; 60])?;
+        let output = dir.path().join("out.txt");
+        let mut opts = Base64EncodeOpts {
+            input: input.to_string_lossy().into_owned(),
+            format: Base64Format::Standard,
+            output: Some(output.to_string_lossy().into_owned()),
+            raw: false,
+            no_pad: false,
+            wrap: None,
+            mime: false,
+            data_uri: false,
+            mime_type: None,
+            pem: Some("TEST DATA".to_string()),
+        };
+        process_encode(&opts)?;
+        let pem = std::fs::read_to_string(&output)?;
+        let lines: Vec<&str> = pem.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "-----BEGIN TEST DATA-----");
+        assert_eq!(lines[1].len(), 64);
+        assert_eq!(lines[3], "-----END TEST DATA-----");
+
+        opts.pem = None;
+        opts.data_uri = true;
+        process_encode(&opts)?;
+        let uri = std::fs::read_to_string(&output)?;
+        assert!(uri.starts_with("data:application/octet-stream;base64,BwcH"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_utf8_tracker_split_char() -> io::Result<()> {
+        let text = "你好".as_bytes();
+        let mut tracker = Utf8Tracker::new(Vec::new(), false);
+        tracker.write_all(&text[..2])?;
+        tracker.write_all(&text[2..])?;
+        assert!(tracker.is_utf8());
+
+        let mut tracker = Utf8Tracker::new(Vec::new(), false);
+        tracker.write_all(&[0xff, 0x00])?;
+        assert!(!tracker.is_utf8());
+        Ok(())
+    }
+
+    #[test]
+    fn test_utf8_tracker_refuses_binary() {
+        let mut tracker = Utf8Tracker::new(Vec::new(), true);
+        assert!(tracker.write_all(b"plain text").is_ok());
+        assert!(tracker.write_all(&[0x00, 0x01]).is_err());
+        assert_eq!(tracker.inner, b"plain text");
+    }
 }
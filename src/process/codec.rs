@@ -0,0 +1,283 @@
+use super::b64::Utf8Tracker;
+use crate::{get_input_bytes, get_writer, Codec, DecodeOpts, EncodeOpts};
+use anyhow::{bail, ensure, Context, Result};
+use base64::prelude::*;
+use data_encoding::{Encoding, Specification, BASE32, BASE32_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE};
+use std::io::{self, IsTerminal, Write};
+
+pub fn process_codec_encode(opts: &EncodeOpts) -> Result<()> {
+    if opts.input == "-" {
+        eprintln!("请输入要编码的内容，按 Ctrl+D (Unix/Mac) 或 Ctrl+Z (Windows) 结束输入：");
+    }
+    let data =
+        get_input_bytes(&opts.input).with_context(|| format!("无法读取输入源 '{}'", opts.input))?;
+    if data.is_empty() {
+        eprintln!("警告：输入内容为空，没有数据需要编码。");
+        return Ok(());
+    }
+
+    let mut writer = get_writer(opts.output.as_deref())?;
+    writer.write_all(encode(opts.codec, &data).as_bytes())?;
+    if !opts.raw {
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn process_codec_decode(opts: &DecodeOpts) -> Result<()> {
+    if opts.input == "-" {
+        eprintln!("请输入要解码的内容，按 Ctrl+D (Unix/Mac) 或 Ctrl+Z (Windows) 结束输入：");
+    }
+    let data =
+        get_input_bytes(&opts.input).with_context(|| format!("无法读取输入源 '{}'", opts.input))?;
+    let text = std::str::from_utf8(&data).context("编码后的输入必须是文本")?;
+    let compact: String = text.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    if compact.is_empty() {
+        eprintln!("警告：输入内容为空，没有数据需要解码。");
+        return Ok(());
+    }
+
+    let decoded = decode(opts.codec, &compact)
+        .with_context(|| format!("{} 解码失败，请检查输入格式是否正确", opts.codec))?;
+
+    let terminal = matches!(opts.output.as_deref(), None | Some("-")) && io::stdout().is_terminal();
+    write_decoded(
+        get_writer(opts.output.as_deref())?,
+        &decoded,
+        terminal,
+        opts.force,
+        opts.raw,
+    )
+}
+
+/// 仅在输出到终端时为 UTF-8 文本补换行；管道与文件保持原始字节，保证 `encode | decode` 能原样往返
+fn write_decoded(
+    writer: impl Write,
+    decoded: &[u8],
+    terminal: bool,
+    force: bool,
+    raw: bool,
+) -> Result<()> {
+    let mut writer = Utf8Tracker::new(writer, terminal && !force);
+    writer.write_all(decoded)?;
+    if terminal && !raw && writer.is_utf8() {
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn encode(codec: Codec, data: &[u8]) -> String {
+    match codec {
+        Codec::Hex => HEXLOWER.encode(data),
+        Codec::Base32 => BASE32.encode(data),
+        Codec::Base32Crockford => crockford().encode(data),
+        Codec::ZBase32 => zbase32().encode(data),
+        Codec::Base58 => bs58::encode(data).into_string(),
+        Codec::Base64 => BASE64_STANDARD.encode(data),
+        Codec::Base64Url => BASE64_URL_SAFE_NO_PAD.encode(data),
+        Codec::Ascii85 => ascii85_encode(data),
+    }
+}
+
+/// 解码前调用方应已去除空白字符；base32 / base64 的填充 `=` 可有可无（TOTP 密钥通常不带填充）
+pub fn decode(codec: Codec, data: &str) -> Result<Vec<u8>> {
+    let decoded = match codec {
+        Codec::Hex => HEXLOWER_PERMISSIVE.decode(data.as_bytes())?,
+        Codec::Base32 => {
+            BASE32_NOPAD.decode(data.trim_end_matches('=').to_ascii_uppercase().as_bytes())?
+        }
+        Codec::Base32Crockford => crockford().decode(data.trim_end_matches('=').as_bytes())?,
+        Codec::ZBase32 => zbase32().decode(data.as_bytes())?,
+        Codec::Base58 => bs58::decode(data).into_vec()?,
+        Codec::Base64 => BASE64_STANDARD_NO_PAD.decode(data.trim_end_matches('='))?,
+        Codec::Base64Url => BASE64_URL_SAFE_NO_PAD.decode(data.trim_end_matches('='))?,
+        Codec::Ascii85 => ascii85_decode(data)?,
+    };
+    Ok(decoded)
+}
+
+/// Crockford Base32：不区分大小写，且把易混淆的 O/I/L 映射为 0/1/1
+fn crockford() -> Encoding {
+    let mut spec = Specification::new();
+    spec.symbols.push_str("0123456789ABCDEFGHJKMNPQRSTVWXYZ");
+    spec.translate.from.push_str("abcdefghjkmnpqrstvwxyzOoIiLl");
+    spec.translate.to.push_str("ABCDEFGHJKMNPQRSTVWXYZ001111");
+    spec.encoding().expect("valid crockford specification")
+}
+
+/// z-base-32（RFC 6189 附录 / Zooko），无填充
+fn zbase32() -> Encoding {
+    let mut spec = Specification::new();
+    spec.symbols.push_str("ybndrfg8ejkmcpqxot1uwisza345h769");
+    spec.encoding().expect("valid z-base-32 specification")
+}
+
+/// Ascii85（btoa / Adobe 变体，不带 `<~ ~>` 定界符），全零分组缩写为 `z`
+fn ascii85_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() * 5 / 4 + 5);
+    for chunk in data.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(group);
+        if chunk.len() == 4 && value == 0 {
+            out.push('z');
+            continue;
+        }
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = (value % 85) as u8 + b'!';
+            value /= 85;
+        }
+        // 不足 4 字节的尾部分组只输出 n + 1 个字符
+        out.extend(digits[..chunk.len() + 1].iter().map(|&d| d as char));
+    }
+    out
+}
+
+fn ascii85_decode(data: &str) -> Result<Vec<u8>> {
+    let data = data.strip_prefix("<~").unwrap_or(data);
+    let data = data.strip_suffix("~>").unwrap_or(data);
+
+    let mut out = Vec::with_capacity(data.len() * 4 / 5);
+    let mut group = [0u8; 5];
+    let mut len = 0;
+    for c in data.bytes() {
+        match c {
+            b'z' if len == 0 => out.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group[len] = c - b'!';
+                len += 1;
+                if len == 5 {
+                    out.extend_from_slice(&ascii85_group(&group)?);
+                    len = 0;
+                }
+            }
+            _ => bail!("Invalid Ascii85 character '{}'", c as char),
+        }
+    }
+    if len > 0 {
+        ensure!(len > 1, "Ascii85 input has a dangling final character");
+        // 用最大值 'u' 补齐尾部分组，再截掉多出的字节
+        group[len..].fill(84);
+        out.extend_from_slice(&ascii85_group(&group)?[..len - 1]);
+    }
+    Ok(out)
+}
+
+fn ascii85_group(group: &[u8; 5]) -> Result<[u8; 4]> {
+    let value = group.iter().fold(0u64, |acc, &d| acc * 85 + d as u64);
+    ensure!(value <= u32::MAX as u64, "Ascii85 group out of range");
+    Ok((value as u32).to_be_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process_otp_secret_new, OtpSecretNewOpts};
+    use clap::Parser;
+
+    const ALL: [Codec; 8] = [
+        Codec::Hex,
+        Codec::Base32,
+        Codec::Base32Crockford,
+        Codec::ZBase32,
+        Codec::Base58,
+        Codec::Base64,
+        Codec::Base64Url,
+        Codec::Ascii85,
+    ];
+
+    #[test]
+    fn test_codec_known_vectors() -> Result<()> {
+        let cases = [
+            (Codec::Hex, &b"hello"[..], "68656c6c6f"),
+            (Codec::Base32, b"foobar", "MZXW6YTBOI======"),
+            (Codec::Base32Crockford, b"foobar", "CSQPYRK1E8"),
+            (Codec::Base58, b"hello world", "StV1DL6CwTryKyV"),
+            (Codec::Ascii85, b"Man is", "9jqo^Bla"),
+            (Codec::Ascii85, &[0, 0, 0, 0, 1], "z!<"),
+        ];
+        for (codec, data, expected) in cases {
+            assert_eq!(encode(codec, data), expected, "{codec}");
+            assert_eq!(decode(codec, expected)?, data, "{codec}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_roundtrip() -> Result<()> {
+        let data: Vec<u8> = (0..=255u8).chain([0, 0, 0, 0, 7]).collect();
+        for codec in ALL {
+            for len in [0, 1, 2, 3, 4, 5, data.len()] {
+                let encoded = encode(codec, &data[..len]);
+                assert_eq!(decode(codec, &encoded)?, &data[..len], "{codec} len {len}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_lenient_variants() -> Result<()> {
+        assert_eq!(decode(Codec::Hex, "68656C6C6F")?, b"hello");
+        assert_eq!(decode(Codec::Base32, "mzxw6ytboi======")?, b"foobar");
+        assert_eq!(decode(Codec::Base32Crockford, "csqpyrkle8")?, b"foobar");
+        assert_eq!(decode(Codec::Ascii85, "<~9jqo^Bla~>")?, b"Man is");
+        assert_eq!(decode(Codec::Base32, "MZXW6YTBOI")?, b"foobar");
+        assert_eq!(decode(Codec::Base64, "Zm9vYg")?, b"foob");
+        assert_eq!(decode(Codec::Base64, "Zm9vYg==")?, b"foob");
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_decodes_otp_secrets() -> Result<()> {
+        // `otp secret new` 输出无填充的 base32
+        for bytes in 10..=14u8 {
+            let opts = OtpSecretNewOpts::try_parse_from(["new", "--bytes", &bytes.to_string()])?;
+            let (secret, _) = process_otp_secret_new(&opts)?;
+            assert_eq!(decode(Codec::Base32, &secret)?.len(), bytes as usize);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decoded_output_is_exact_when_piped() -> Result<()> {
+        for data in [&b"hello"[..], &[0xff, 0x00, 0x0a]] {
+            let mut out = Vec::new();
+            write_decoded(&mut out, data, false, false, false)?;
+            assert_eq!(out, data);
+        }
+
+        // 终端上的文本补换行，--raw 则不补；二进制默认拒绝输出
+        let mut out = Vec::new();
+        write_decoded(&mut out, b"hello", true, false, false)?;
+        assert_eq!(out, b"hello\n");
+        let mut out = Vec::new();
+        write_decoded(&mut out, b"hello", true, false, true)?;
+        assert_eq!(out, b"hello");
+        assert!(write_decoded(Vec::new(), &[0xff], true, false, false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_invalid_input() {
+        assert!(decode(Codec::Hex, "abc").is_err());
+        assert!(decode(Codec::Base58, "0OIl").is_err());
+        assert!(decode(Codec::Ascii85, "9jqo^B").is_err());
+        assert!(decode(Codec::Ascii85, "vvvvv")
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid Ascii85 character"));
+
+        // "s8W-!" 即 u32::MAX，再大一位的分组必须报范围错误
+        assert_eq!(decode(Codec::Ascii85, "s8W-!").unwrap(), [0xff; 4]);
+        for overflow in ["s8W-\"", "uuuuu"] {
+            let err = decode(Codec::Ascii85, overflow).unwrap_err();
+            assert!(
+                err.to_string().contains("out of range"),
+                "{overflow}: {err}"
+            );
+        }
+    }
+}
@@ -0,0 +1,357 @@
+//! `rst text encrypt` 的密文容器格式；头部原样作为 AEAD 关联数据，任何字段被篡改都会导致解密失败
+//!
+//! ```text
+//! version 2: "RSTE"(4) || version(1) || cipher(1) || kdf(1) || flags(1) || [kdf 参数] || [segment(4, LE)] || nonce || 密文
+//! version 1: "RSTE"(4) || version(1) || kdf(1) || [kdf 参数] || nonce(12) || 密文
+//! kdf 参数:  m_cost(4, LE) || t_cost(4, LE) || p_cost(4, LE) || salt(16)，仅 Argon2id 存在
+//! ```
+//!
+//! - cipher：1 = ChaCha20-Poly1305（nonce 12 字节），2 = XChaCha20-Poly1305（24 字节），
+//!   3 = AES-256-GCM（12 字节）；version 1 固定为 ChaCha20-Poly1305
+//! - kdf：0 = 原始 32 字节密钥，1 = Argon2id
+//! - flags：bit 0 表示加密时绑定了外部关联数据（此时 AEAD 关联数据为 `头部 || 外部数据`，解密时必须提供相同数据）；bit 1 表示分段（STREAM）加密，其余位保留且必须为 0
+//! - 分段加密：头部记录明文分段大小，nonce 为 STREAM 前缀（nonce 长度 - 5 字节），
+//!   之后每个分段独立认证（`前缀 || 计数器(4, BE) || 末段标记(1)`），能发现截断、重排与拼接
+//! - 未知的 version、cipher、kdf 或 flags 均直接拒绝
+//! - 没有魔数的数据视为最早的 `nonce(12) || 密文` 格式，由调用方处理
+
+use crate::cli::TextCipher;
+use anyhow::{bail, ensure, Context, Result};
+use std::io::Read;
+
+pub const MAGIC: &[u8; 4] = b"RSTE";
+pub const CURRENT_VERSION: u8 = 2;
+/// 加密时绑定了外部关联数据，解密时必须提供相同数据
+pub const FLAG_AAD: u8 = 0b0000_0001;
+/// 分段加密，由 `CipherHeader::segment_size` 决定，不在 `flags` 字段中暴露
+const FLAG_STREAM: u8 = 0b0000_0010;
+const KNOWN_FLAGS: u8 = FLAG_AAD | FLAG_STREAM;
+const KDF_RAW: u8 = 0;
+const KDF_ARGON2ID: u8 = 1;
+pub const SALT_LEN: usize = 16;
+/// AEAD 认证标签长度，分段加密的每段密文比明文多出该长度
+pub const TAG_LEN: usize = 16;
+/// 解密时接受的 Argon2 内存上限（KiB），防止伪造头部耗尽内存
+pub const MAX_KDF_MEMORY: u32 = 4 * 1024 * 1024;
+/// 迭代次数与并行度上限，防止伪造头部让派生几乎无法结束
+pub const MAX_KDF_ITERATIONS: u32 = 64;
+pub const MAX_KDF_PARALLELISM: u32 = 64;
+/// 分段加密默认的明文分段大小
+pub const DEFAULT_SEGMENT_SIZE: u32 = 64 * 1024;
+const MAX_SEGMENT_SIZE: u32 = 16 * 1024 * 1024;
+/// STREAM nonce 中计数器（4 字节）与末段标记（1 字节）的长度
+const STREAM_NONCE_OVERHEAD: usize = 5;
+
+/// Argon2id 代价参数，写入密文头部，解密时按头部复现
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// 内存（KiB）
+    pub memory: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KdfSpec {
+    Raw,
+    Argon2id {
+        params: KdfParams,
+        salt: [u8; SALT_LEN],
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CipherHeader {
+    pub version: u8,
+    pub cipher: TextCipher,
+    pub kdf: KdfSpec,
+    pub flags: u8,
+    /// 分段加密的明文分段大小；`None` 表示整体加密
+    pub segment_size: Option<u32>,
+    /// 整体加密时为完整 nonce，分段加密时为 STREAM 前缀
+    pub nonce: Vec<u8>,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory: 65536,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// 拒绝超出上限的代价参数；头部来自不可信输入，必须在派生前检查
+    pub fn check_limits(&self) -> Result<()> {
+        ensure!(
+            self.memory <= MAX_KDF_MEMORY,
+            "Argon2 memory cost {} KiB exceeds the {} KiB limit",
+            self.memory,
+            MAX_KDF_MEMORY
+        );
+        ensure!(
+            self.iterations <= MAX_KDF_ITERATIONS,
+            "Argon2 iteration count {} exceeds the limit of {}",
+            self.iterations,
+            MAX_KDF_ITERATIONS
+        );
+        ensure!(
+            self.parallelism <= MAX_KDF_PARALLELISM,
+            "Argon2 parallelism {} exceeds the limit of {}",
+            self.parallelism,
+            MAX_KDF_PARALLELISM
+        );
+        Ok(())
+    }
+}
+
+impl TextCipher {
+    pub fn nonce_len(self) -> usize {
+        match self {
+            TextCipher::ChaCha20Poly1305 | TextCipher::Aes256Gcm => 12,
+            TextCipher::XChaCha20Poly1305 => 24,
+        }
+    }
+}
+
+impl TryFrom<u8> for TextCipher {
+    type Error = anyhow::Error;
+
+    fn try_from(id: u8) -> Result<Self> {
+        match id {
+            1 => Ok(TextCipher::ChaCha20Poly1305),
+            2 => Ok(TextCipher::XChaCha20Poly1305),
+            3 => Ok(TextCipher::Aes256Gcm),
+            _ => bail!("Unknown cipher id {}", id),
+        }
+    }
+}
+
+impl From<TextCipher> for u8 {
+    fn from(cipher: TextCipher) -> Self {
+        match cipher {
+            TextCipher::ChaCha20Poly1305 => 1,
+            TextCipher::XChaCha20Poly1305 => 2,
+            TextCipher::Aes256Gcm => 3,
+        }
+    }
+}
+
+impl CipherHeader {
+    /// 以当前版本创建头部
+    pub fn new(cipher: TextCipher, kdf: KdfSpec, flags: u8, nonce: Vec<u8>) -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            cipher,
+            kdf,
+            flags,
+            segment_size: None,
+            nonce,
+        }
+    }
+
+    /// 头部中 nonce 字段的长度
+    pub fn nonce_len(&self) -> usize {
+        match self.segment_size {
+            Some(_) => self.cipher.nonce_len() - STREAM_NONCE_OVERHEAD,
+            None => self.cipher.nonce_len(),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + 12 + SALT_LEN + self.nonce.len());
+        out.extend_from_slice(MAGIC);
+        out.push(self.version);
+        if self.version >= 2 {
+            out.push(self.cipher.into());
+        }
+        out.push(match self.kdf {
+            KdfSpec::Raw => KDF_RAW,
+            KdfSpec::Argon2id { .. } => KDF_ARGON2ID,
+        });
+        if self.version >= 2 {
+            let stream = if self.segment_size.is_some() {
+                FLAG_STREAM
+            } else {
+                0
+            };
+            out.push(self.flags | stream);
+        }
+        if let KdfSpec::Argon2id { params, salt } = &self.kdf {
+            for value in [params.memory, params.iterations, params.parallelism] {
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+            out.extend_from_slice(salt);
+        }
+        if let Some(segment_size) = self.segment_size {
+            out.extend_from_slice(&segment_size.to_le_bytes());
+        }
+        out.extend_from_slice(&self.nonce);
+        out
+    }
+
+    /// 解析头部并返回头部长度；数据不以魔数开头时返回 `None`（旧格式）
+    pub fn parse(data: &[u8]) -> Result<Option<(Self, usize)>> {
+        if !data.starts_with(MAGIC) {
+            return Ok(None);
+        }
+        let (header, raw) = Self::read_from(&mut &data[..])?;
+        Ok(Some((header, raw.len())))
+    }
+
+    /// 从流中读取完整头部，同时返回头部原始字节（用作关联数据）
+    pub fn read_from(reader: &mut dyn Read) -> Result<(Self, Vec<u8>)> {
+        let mut reader = HeaderReader {
+            reader,
+            bytes: Vec::new(),
+        };
+        ensure!(
+            reader.take(MAGIC.len())? == MAGIC,
+            "Input is not rst ciphertext (missing RSTE magic)"
+        );
+        let version = reader.u8()?;
+        let (cipher, kdf_id, flags) = match version {
+            1 => (TextCipher::ChaCha20Poly1305, reader.u8()?, 0),
+            2 => (reader.u8()?.try_into()?, reader.u8()?, reader.u8()?),
+            _ => bail!(
+                "Unsupported ciphertext version {} (this build reads up to {})",
+                version,
+                CURRENT_VERSION
+            ),
+        };
+        ensure!(
+            flags & !KNOWN_FLAGS == 0,
+            "Unknown ciphertext flags {:#04x}",
+            flags
+        );
+        let kdf = match kdf_id {
+            KDF_RAW => KdfSpec::Raw,
+            KDF_ARGON2ID => {
+                let params = KdfParams {
+                    memory: reader.u32()?,
+                    iterations: reader.u32()?,
+                    parallelism: reader.u32()?,
+                };
+                params.check_limits()?;
+                let salt = reader.take(SALT_LEN)?.try_into()?;
+                KdfSpec::Argon2id { params, salt }
+            }
+            _ => bail!("Unknown key derivation id {}", kdf_id),
+        };
+        let segment_size = if flags & FLAG_STREAM != 0 {
+            let size = reader.u32()?;
+            ensure!(
+                size > 0 && size <= MAX_SEGMENT_SIZE,
+                "Invalid stream segment size {}",
+                size
+            );
+            Some(size)
+        } else {
+            None
+        };
+        let mut header = Self {
+            version,
+            cipher,
+            kdf,
+            flags: flags & !FLAG_STREAM,
+            segment_size,
+            nonce: Vec::new(),
+        };
+        header.nonce = reader.take(header.nonce_len())?.to_vec();
+        Ok((header, reader.bytes))
+    }
+}
+
+struct HeaderReader<'a> {
+    reader: &'a mut dyn Read,
+    bytes: Vec<u8>,
+}
+
+impl HeaderReader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        let start = self.bytes.len();
+        self.bytes.resize(start + len, 0);
+        self.reader
+            .read_exact(&mut self.bytes[start..])
+            .context("Ciphertext header is truncated")?;
+        Ok(&self.bytes[start..])
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_roundtrip() -> Result<()> {
+        let kdf = KdfSpec::Argon2id {
+            params: KdfParams::default(),
+            salt: [3u8; SALT_LEN],
+        };
+        for version in [1, CURRENT_VERSION] {
+            let header = CipherHeader {
+                version,
+                ..CipherHeader::new(TextCipher::ChaCha20Poly1305, kdf.clone(), 0, vec![9u8; 12])
+            };
+            let mut data = header.encode();
+            let len = data.len();
+            data.extend_from_slice(b"payload");
+            assert_eq!(CipherHeader::parse(&data)?, Some((header, len)));
+        }
+        let stream = CipherHeader {
+            segment_size: Some(DEFAULT_SEGMENT_SIZE),
+            ..CipherHeader::new(TextCipher::ChaCha20Poly1305, kdf, FLAG_AAD, vec![1u8; 7])
+        };
+        let data = stream.encode();
+        assert_eq!(CipherHeader::parse(&data)?, Some((stream, data.len())));
+        assert_eq!(CipherHeader::parse(&[0u8; 32])?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_header_rejects_unknown_fields() {
+        let header = CipherHeader::new(TextCipher::ChaCha20Poly1305, KdfSpec::Raw, 0, vec![0; 12]);
+        let data = header.encode();
+        // version、cipher、kdf、flags 依次位于魔数之后
+        for (index, value) in [(4, 3), (5, 9), (6, 7), (7, 0x80)] {
+            let mut tampered = data.clone();
+            tampered[index] = value;
+            assert!(CipherHeader::parse(&tampered).is_err());
+        }
+        assert!(CipherHeader::parse(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_header_rejects_excessive_kdf_costs() {
+        let salt = [0u8; SALT_LEN];
+        for params in [
+            KdfParams {
+                memory: MAX_KDF_MEMORY + 1,
+                ..KdfParams::default()
+            },
+            KdfParams {
+                iterations: MAX_KDF_ITERATIONS + 1,
+                ..KdfParams::default()
+            },
+            KdfParams {
+                parallelism: MAX_KDF_PARALLELISM + 1,
+                ..KdfParams::default()
+            },
+        ] {
+            let kdf = KdfSpec::Argon2id { params, salt };
+            let data =
+                CipherHeader::new(TextCipher::ChaCha20Poly1305, kdf, 0, vec![0; 12]).encode();
+            assert!(CipherHeader::parse(&data).is_err());
+        }
+    }
+}
@@ -1,26 +1,31 @@
-use crate::GenPassOpts;
+use crate::{GenPassDeriveOpts, GenPassOpts};
+use anyhow::{bail, ensure, Context};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::rngs::ThreadRng;
 use rand::seq::{IndexedRandom, SliceRandom};
-use std::iter;
+use rand::Rng;
+use secrecy::{ExposeSecret, SecretString};
+use std::{env, io::Read, iter};
+use zeroize::Zeroizing;
 
 const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
 const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
 const NUMBERS: &[u8] = b"0123456789";
 const SYMBOLS: &[u8] = b"!@#$%^&*().<>?_+-=/";
+const CONSONANTS: &[u8] = b"bcdfghjklmnprstvwxz";
+const VOWELS: &[u8] = b"aeiou";
 
 pub fn process_genpass(opts: &GenPassOpts) -> anyhow::Result<String> {
     let mut rng = rand::rng();
 
-    let char_sets = [
-        (opts.lowercase, LOWERCASE),
-        (opts.uppercase, UPPERCASE),
-        (opts.numbers, NUMBERS),
-        (opts.symbols, SYMBOLS),
-    ];
-    let enabled_sets: Vec<&[u8]> = char_sets
-        .into_iter() // 使用 into_iter 消费数组
-        .filter(|(enabled, _)| *enabled)
-        .map(|(_, set)| set)
-        .collect();
+    if let Some(pattern) = &opts.pattern {
+        return gen_from_pattern(pattern, &mut rng);
+    }
+    if opts.pronounceable {
+        return gen_pronounceable(opts, &mut rng);
+    }
+
+    let enabled_sets = enabled_sets(opts);
 
     // 3. 在所有操作开始前，首先处理边界情况
     if enabled_sets.is_empty() {
@@ -49,3 +54,374 @@ pub fn process_genpass(opts: &GenPassOpts) -> anyhow::Result<String> {
     let final_password = String::from_utf8(password)?;
     Ok(final_password)
 }
+
+fn enabled_sets(opts: &GenPassOpts) -> Vec<&'static [u8]> {
+    let char_sets = [
+        (opts.lowercase, LOWERCASE),
+        (opts.uppercase, UPPERCASE),
+        (opts.numbers, NUMBERS),
+        (opts.symbols, SYMBOLS),
+    ];
+    char_sets
+        .into_iter() // 使用 into_iter 消费数组
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, set)| set)
+        .collect()
+}
+
+/// 从主密码确定性地派生站点密码
+///
+/// Argon2id(master, site/user/counter) 得到 32 字节种子，再以 BLAKE3 XOF 展开为字节流，
+/// 通过拒绝采样映射到与 `process_genpass` 相同的字符集规则上，避免取模偏差。
+pub fn process_genpass_derive(
+    opts: &GenPassOpts,
+    derive: &GenPassDeriveOpts,
+) -> anyhow::Result<String> {
+    ensure!(
+        opts.pattern.is_none() && !opts.pronounceable,
+        "derive only supports the character-class options"
+    );
+    let enabled_sets = enabled_sets(opts);
+    ensure!(!enabled_sets.is_empty(), "请至少选择一个字符集！");
+
+    let master = resolve_master(&derive.master)?;
+    let params = Params::new(
+        derive.memory,
+        derive.iterations,
+        derive.parallelism,
+        Some(32),
+    )
+    .map_err(|e| anyhow::anyhow!("Invalid Argon2 parameters: {e}"))?;
+    // 种子即所有派生密码的主密钥，用完清零
+    let mut seed = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(
+            master.expose_secret().as_bytes(),
+            &derive_salt(derive),
+            seed.as_mut_slice(),
+        )
+        .map_err(|e| anyhow::anyhow!("Argon2 derivation failed: {e}"))?;
+
+    let mut stream = DerivedStream(blake3::Hasher::new_keyed(&seed).finalize_xof());
+
+    let mut password: Vec<u8> = enabled_sets
+        .iter()
+        .map(|set| set[stream.below(set.len())])
+        .collect();
+    let charset: Vec<u8> = enabled_sets.iter().flat_map(|set| *set).cloned().collect();
+    let remaining_len = opts.length.saturating_sub(password.len());
+    password.extend(iter::repeat_with(|| charset[stream.below(charset.len())]).take(remaining_len));
+
+    // Fisher-Yates 洗牌，索引同样来自派生字节流
+    for i in (1..password.len()).rev() {
+        password.swap(i, stream.below(i + 1));
+    }
+
+    Ok(String::from_utf8(password)?)
+}
+
+fn resolve_master(master: &Option<SecretString>) -> anyhow::Result<SecretString> {
+    let master = match master {
+        Some(value) => value.clone(),
+        None => SecretString::from(env::var("RST_MASTER_PASSWORD").context(
+            "Master passphrase missing; supply --master or set RST_MASTER_PASSWORD env var",
+        )?),
+    };
+    ensure!(
+        !master.expose_secret().is_empty(),
+        "Master passphrase cannot be empty"
+    );
+    Ok(master)
+}
+
+/// 以长度前缀拼接各字段，避免 ("ab", "c") 与 ("a", "bc") 产生相同的盐
+///
+/// 域名不区分大小写，因此 site 去除首尾空白并转为小写；user 原样使用，
+/// 因为不少站点的用户名区分大小写，`Alice` 与 `alice` 可能是两个账号。
+fn derive_salt(derive: &GenPassDeriveOpts) -> Vec<u8> {
+    let site = derive.site.trim().to_ascii_lowercase();
+    let mut salt = b"rst-genpass-derive-v1".to_vec();
+    for field in [site.as_bytes(), derive.user.as_bytes()] {
+        salt.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        salt.extend_from_slice(field);
+    }
+    salt.extend_from_slice(&derive.counter.to_be_bytes());
+    salt
+}
+
+struct DerivedStream(blake3::OutputReader);
+
+impl DerivedStream {
+    /// 返回 [0, n) 内均匀分布的索引，超出 256 - 256 % n 的字节直接丢弃
+    fn below(&mut self, n: usize) -> usize {
+        debug_assert!(n > 0 && n <= 256);
+        let limit = 256 - 256 % n;
+        loop {
+            let mut byte = [0u8; 1];
+            self.0
+                .read_exact(&mut byte)
+                .expect("BLAKE3 XOF never runs out");
+            if (byte[0] as usize) < limit {
+                return byte[0] as usize % n;
+            }
+        }
+    }
+}
+
+/// 按音节（辅音 + 元音）生成便于朗读的密码
+///
+/// 同时启用大小写时每个音节的辅音随机大写；启用数字/符号时音节之间插入一个分隔字符，
+/// 且每个启用的字符集至少出现一次（长度允许时）。
+fn gen_pronounceable(opts: &GenPassOpts, rng: &mut ThreadRng) -> anyhow::Result<String> {
+    if !opts.lowercase && !opts.uppercase {
+        eprintln!("错误：可朗读模式至少需要启用大写或小写字母！");
+        return Ok(String::new());
+    }
+    let extras: Vec<&[u8]> = [(opts.numbers, NUMBERS), (opts.symbols, SYMBOLS)]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, set)| set)
+        .collect();
+
+    let mut password = Vec::with_capacity(opts.length + 1);
+    let mut separators = Vec::new();
+    let mut syllable_starts = Vec::new();
+    while password.len() < opts.length {
+        // 分隔符后至少还要放下一个字母，避免以数字/符号结尾
+        if !password.is_empty() && !extras.is_empty() && password.len() + 1 < opts.length {
+            separators.push(password.len());
+            password.push(0);
+        }
+        syllable_starts.push(password.len());
+        password.push(*CONSONANTS.choose(rng).unwrap());
+        password.push(*VOWELS.choose(rng).unwrap());
+    }
+    password.truncate(opts.length);
+
+    if !opts.lowercase {
+        password.make_ascii_uppercase();
+    } else if opts.uppercase {
+        for &start in &syllable_starts {
+            if rng.random_bool(0.5) {
+                password[start].make_ascii_uppercase();
+            }
+        }
+        if !password.iter().any(u8::is_ascii_uppercase) {
+            if let Some(&start) = syllable_starts.choose(rng) {
+                password[start].make_ascii_uppercase();
+            }
+        }
+    }
+
+    // 先保证每个字符集各占一个分隔位，其余随机，再打乱分配顺序
+    let mut sets: Vec<&[u8]> = extras.clone();
+    while sets.len() < separators.len() {
+        sets.push(extras.choose(rng).unwrap());
+    }
+    sets.truncate(separators.len());
+    sets.shuffle(rng);
+    for (&pos, set) in separators.iter().zip(sets) {
+        password[pos] = *set.choose(rng).unwrap();
+    }
+
+    Ok(String::from_utf8(password)?)
+}
+
+/// 按模板生成密码，每个占位符对应一个字符集，其余字符原样保留
+///
+/// * `C`/`c` - 大写/小写辅音
+/// * `V`/`v` - 大写/小写元音
+/// * `A`/`a` - 大写/小写字母
+/// * `9` - 数字，`#` - 符号，`*` - 任意字符
+/// * `\` - 转义下一个字符
+fn gen_from_pattern(pattern: &str, rng: &mut ThreadRng) -> anyhow::Result<String> {
+    let mut password = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        let picked = match c {
+            'c' => pick(CONSONANTS, rng),
+            'C' => pick(CONSONANTS, rng).to_ascii_uppercase(),
+            'v' => pick(VOWELS, rng),
+            'V' => pick(VOWELS, rng).to_ascii_uppercase(),
+            'a' => pick(LOWERCASE, rng),
+            'A' => pick(UPPERCASE, rng),
+            '9' => pick(NUMBERS, rng),
+            '#' => pick(SYMBOLS, rng),
+            '*' => pick(&[LOWERCASE, UPPERCASE, NUMBERS, SYMBOLS].concat(), rng),
+            '\\' => match chars.next() {
+                Some(escaped) => escaped,
+                None => bail!("模板末尾的转义符 '\\' 缺少后续字符"),
+            },
+            literal => literal,
+        };
+        password.push(picked);
+    }
+
+    Ok(password)
+}
+
+fn pick(set: &[u8], rng: &mut ThreadRng) -> char {
+    *set.choose(rng).unwrap() as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derive_opts(counter: u32) -> GenPassDeriveOpts {
+        GenPassDeriveOpts {
+            site: "example.com".to_string(),
+            user: "alice".to_string(),
+            counter,
+            master: Some("correct horse battery staple".into()),
+            memory: 1024,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
+    #[test]
+    fn test_genpass_derive_is_stable() -> anyhow::Result<()> {
+        let opts = GenPassOpts {
+            length: 20,
+            ..GenPassOpts::get_default_opts()
+        };
+        let first = process_genpass_derive(&opts, &derive_opts(1))?;
+        let second = process_genpass_derive(&opts, &derive_opts(1))?;
+        let rotated = process_genpass_derive(&opts, &derive_opts(2))?;
+        assert_eq!(first, second);
+        assert_ne!(first, rotated);
+        assert_eq!(first.len(), 20);
+        for set in [LOWERCASE, UPPERCASE, NUMBERS, SYMBOLS] {
+            assert!(first.bytes().any(|c| set.contains(&c)));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_genpass_derive_known_answers() -> anyhow::Result<()> {
+        // 固定输入的输出一旦改变，所有用户的派生密码都会改变，依赖升级时必须保持一致
+        let opts = GenPassOpts {
+            length: 20,
+            ..GenPassOpts::get_default_opts()
+        };
+        let cases = [
+            ("example.com", "alice", 1, "EwWFs1_Ev9rcW8_plZC$"),
+            ("example.com", "alice", 2, "0i9o0II&%aDt(usoP#1p"),
+            (" Example.COM ", "alice", 1, "EwWFs1_Ev9rcW8_plZC$"),
+            ("example.com", "Alice", 1, "o>)-y)zVcU88aa_3XCdb"),
+        ];
+        for (site, user, counter, expected) in cases {
+            let derive = GenPassDeriveOpts {
+                site: site.to_string(),
+                user: user.to_string(),
+                ..derive_opts(counter)
+            };
+            assert_eq!(
+                process_genpass_derive(&opts, &derive)?,
+                expected,
+                "{site}/{user}/{counter}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_genpass_derive_respects_char_sets() -> anyhow::Result<()> {
+        let opts = GenPassOpts {
+            symbols: false,
+            uppercase: false,
+            ..GenPassOpts::get_default_opts()
+        };
+        let password = process_genpass_derive(&opts, &derive_opts(1))?;
+        assert!(password
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_genpass_pronounceable() -> anyhow::Result<()> {
+        // 仅小写字母时为纯粹的辅音/元音交替
+        let opts = GenPassOpts {
+            length: 10,
+            pronounceable: true,
+            uppercase: false,
+            numbers: false,
+            symbols: false,
+            ..GenPassOpts::get_default_opts()
+        };
+        let password = process_genpass(&opts)?;
+        assert_eq!(password.len(), 10);
+        for (i, c) in password.bytes().enumerate() {
+            let set = if i % 2 == 0 { CONSONANTS } else { VOWELS };
+            assert!(set.contains(&c), "unexpected char {} at {}", c as char, i);
+        }
+
+        // 默认字符集：音节之间插入数字或符号，且大小写、数字、符号均出现
+        let opts = GenPassOpts {
+            length: 14,
+            pronounceable: true,
+            ..GenPassOpts::get_default_opts()
+        };
+        for _ in 0..20 {
+            let password = process_genpass(&opts)?;
+            let bytes = password.as_bytes();
+            assert_eq!(bytes.len(), 14);
+            for (i, c) in bytes.iter().enumerate() {
+                let set = match i % 3 {
+                    0 => CONSONANTS,
+                    1 => VOWELS,
+                    _ => &[NUMBERS, SYMBOLS].concat(),
+                };
+                let c = c.to_ascii_lowercase();
+                assert!(
+                    set.contains(&c),
+                    "unexpected char {} in {}",
+                    c as char,
+                    password
+                );
+            }
+            for set in [LOWERCASE, UPPERCASE, NUMBERS, SYMBOLS] {
+                assert!(bytes.iter().any(|c| set.contains(c)), "{password}");
+            }
+        }
+
+        let opts = GenPassOpts {
+            pronounceable: true,
+            lowercase: false,
+            ..GenPassOpts::get_default_opts()
+        };
+        let password = process_genpass(&opts)?;
+        assert!(!password.bytes().any(|c| c.is_ascii_lowercase()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_genpass_pattern() -> anyhow::Result<()> {
+        let opts = GenPassOpts {
+            pattern: Some("Cvccvc-99-#\\9".to_string()),
+            ..GenPassOpts::get_default_opts()
+        };
+        let password = process_genpass(&opts)?;
+        let bytes = password.as_bytes();
+        assert_eq!(bytes.len(), 12);
+        assert!(bytes[0].is_ascii_uppercase());
+        assert!(VOWELS.contains(&bytes[1]));
+        assert_eq!(bytes[6], b'-');
+        assert!(bytes[7].is_ascii_digit() && bytes[8].is_ascii_digit());
+        assert!(SYMBOLS.contains(&bytes[10]));
+        assert_eq!(bytes[11], b'9');
+        Ok(())
+    }
+
+    #[test]
+    fn test_genpass_pattern_trailing_escape() {
+        let opts = GenPassOpts {
+            pattern: Some("Cvc\\".to_string()),
+            ..GenPassOpts::get_default_opts()
+        };
+        assert!(process_genpass(&opts).is_err());
+    }
+}
@@ -0,0 +1,347 @@
+use crate::{get_input_string, get_reader, HashAlgorithm};
+use anyhow::{bail, Context, Result};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use sha3::{Sha3_256, Sha3_512};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Stream `reader` through the selected algorithm and return the lowercase hex digest
+pub fn hash_reader(reader: &mut dyn Read, algorithm: HashAlgorithm) -> Result<String> {
+    fn digest<D: Digest + Write>(reader: &mut dyn Read) -> Result<String> {
+        let mut hasher = D::new();
+        io::copy(reader, &mut hasher)?;
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    match algorithm {
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            io::copy(reader, &mut hasher)?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+        HashAlgorithm::Sha256 => digest::<Sha256>(reader),
+        HashAlgorithm::Sha512 => digest::<Sha512>(reader),
+        HashAlgorithm::Sha3_256 => digest::<Sha3_256>(reader),
+        HashAlgorithm::Sha3_512 => digest::<Sha3_512>(reader),
+        HashAlgorithm::Sha1 => digest::<Sha1>(reader),
+        HashAlgorithm::Md5 => digest::<Md5>(reader),
+    }
+}
+
+fn hash_input(input: &str, algorithm: HashAlgorithm) -> Result<String> {
+    let mut reader = get_reader(input).with_context(|| format!("无法读取输入源 '{}'", input))?;
+    hash_reader(&mut *reader, algorithm)
+}
+
+/// Hash every input on up to `jobs` worker threads, returning results in input order.
+/// stdin (`-`) can only be read once, so a repeated `-` fails for every occurrence.
+pub fn process_hash(
+    inputs: &[String],
+    algorithm: HashAlgorithm,
+    jobs: usize,
+) -> Vec<Result<String>> {
+    let next = AtomicUsize::new(0);
+    let repeated_stdin = inputs.iter().filter(|input| *input == "-").count() > 1;
+    let results: Mutex<Vec<Option<Result<String>>>> = Mutex::new(
+        inputs
+            .iter()
+            .map(|input| {
+                (repeated_stdin && input == "-")
+                    .then(|| Err(anyhow::anyhow!("标准输入 '-' 只能出现一次")))
+            })
+            .collect(),
+    );
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, inputs.len().max(1)) {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(input) = inputs.get(idx) else {
+                    break;
+                };
+                if repeated_stdin && input == "-" {
+                    continue;
+                }
+                let digest = hash_input(input, algorithm);
+                results.lock().unwrap()[idx] = Some(digest);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every input is hashed"))
+        .collect()
+}
+
+/// Verify a `sha256sum`-style checksum file (`<hex>  <name>` or `<hex> *<name>` per line).
+/// Without an explicit `algorithm` it is inferred from the file name or the digest length.
+pub fn process_hash_check(
+    sums: &str,
+    algorithm: Option<HashAlgorithm>,
+    jobs: usize,
+) -> Result<Vec<(String, Result<bool>)>> {
+    let content = get_input_string(sums).with_context(|| format!("无法读取校验文件 '{}'", sums))?;
+    let entries = parse_sums(&content)?;
+    let stdin_entries = entries.iter().filter(|(_, name)| name == "-").count();
+    if stdin_entries > 1 || (stdin_entries == 1 && sums == "-") {
+        bail!("标准输入 '-' 在校验中只能使用一次（校验文件本身也从标准输入读取时不能再校验 '-'）");
+    }
+    let algorithm = match algorithm {
+        Some(algorithm) => algorithm,
+        None => infer_algorithm(sums, &entries)?,
+    };
+    for (digest, name) in &entries {
+        if digest.len() != hex_len(algorithm) {
+            bail!(
+                "'{}' 的摘要长度为 {} 个十六进制字符，与 {} 不符（应为 {}）",
+                name,
+                digest.len(),
+                algorithm,
+                hex_len(algorithm)
+            );
+        }
+    }
+    let names: Vec<String> = entries.iter().map(|(_, name)| name.clone()).collect();
+    let digests = process_hash(&names, algorithm, jobs);
+
+    Ok(entries
+        .into_iter()
+        .zip(digests)
+        .map(|((expected, name), digest)| {
+            let matched = digest.map(|digest| digest.eq_ignore_ascii_case(&expected));
+            (name, matched)
+        })
+        .collect())
+}
+
+fn hex_len(algorithm: HashAlgorithm) -> usize {
+    match algorithm {
+        HashAlgorithm::Md5 => 32,
+        HashAlgorithm::Sha1 => 40,
+        HashAlgorithm::Blake3 | HashAlgorithm::Sha256 | HashAlgorithm::Sha3_256 => 64,
+        HashAlgorithm::Sha512 | HashAlgorithm::Sha3_512 => 128,
+    }
+}
+
+/// 先按文件名（`SHA256SUMS`、`*.sha512`、`b3sums.txt` 等）推断，否则仅在摘要长度唯一对应一种算法时采用
+///
+/// 文件名按 `.` 切分，每段整体或其最后一个 `-`/`_` 分隔部分去掉 `sums`/`sum` 后缀后须与算法名完全一致，
+/// 避免 `app-1.0b3.sums` 之类的名字被误判。
+fn infer_algorithm(sums: &str, entries: &[(String, String)]) -> Result<HashAlgorithm> {
+    const NAMES: [(&str, HashAlgorithm); 10] = [
+        ("sha3-256", HashAlgorithm::Sha3_256),
+        ("sha3_256", HashAlgorithm::Sha3_256),
+        ("sha3-512", HashAlgorithm::Sha3_512),
+        ("sha3_512", HashAlgorithm::Sha3_512),
+        ("sha512", HashAlgorithm::Sha512),
+        ("sha256", HashAlgorithm::Sha256),
+        ("sha1", HashAlgorithm::Sha1),
+        ("md5", HashAlgorithm::Md5),
+        ("blake3", HashAlgorithm::Blake3),
+        ("b3", HashAlgorithm::Blake3),
+    ];
+    let file_name = Path::new(sums)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let named = file_name
+        .split('.')
+        .flat_map(|token| [token, token.rsplit(['-', '_']).next().unwrap_or(token)])
+        .map(|token| {
+            token
+                .strip_suffix("sums")
+                .or_else(|| token.strip_suffix("sum"))
+                .unwrap_or(token)
+        })
+        .find_map(|token| NAMES.iter().find(|(key, _)| *key == token));
+    if let Some((_, algorithm)) = named {
+        return Ok(*algorithm);
+    }
+
+    match entries.first().map(|(digest, _)| digest.len()) {
+        Some(32) => Ok(HashAlgorithm::Md5),
+        Some(40) => Ok(HashAlgorithm::Sha1),
+        Some(len) => bail!(
+            "无法从校验文件 '{}' 推断摘要算法（{} 个十六进制字符可能对应多种算法），请使用 -a/--algorithm 指定",
+            sums,
+            len
+        ),
+        None => bail!("校验文件 '{}' 中没有任何条目", sums),
+    }
+}
+
+fn parse_sums(content: &str) -> Result<Vec<(String, String)>> {
+    let mut entries = Vec::new();
+    for (lineno, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((digest, name)) = line.split_once(' ') else {
+            bail!("第 {} 行格式不正确：{}", lineno + 1, line);
+        };
+        // GNU 格式：文本模式为两个空格，二进制模式为 " *"
+        let name = name
+            .strip_prefix(' ')
+            .or_else(|| name.strip_prefix('*'))
+            .unwrap_or(name);
+        if digest.is_empty() || !digest.bytes().all(|b| b.is_ascii_hexdigit()) || name.is_empty() {
+            bail!("第 {} 行格式不正确：{}", lineno + 1, line);
+        }
+        entries.push((digest.to_string(), name.to_string()));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_reader_known_digests() -> Result<()> {
+        let cases = [
+            (
+                HashAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Sha1,
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+            ),
+            (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (
+                HashAlgorithm::Sha3_256,
+                "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            ),
+            (
+                HashAlgorithm::Blake3,
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+        ];
+        for (algorithm, expected) in cases {
+            assert_eq!(hash_reader(&mut &b"abc"[..], algorithm)?, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_hash_keeps_order() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let inputs: Vec<String> = (0..8)
+            .map(|i| {
+                let path = dir.path().join(format!("{i}.txt"));
+                std::fs::write(&path, i.to_string()).unwrap();
+                path.to_string_lossy().into_owned()
+            })
+            .collect();
+        let results = process_hash(&inputs, HashAlgorithm::Sha256, 3);
+        for (i, result) in results.into_iter().enumerate() {
+            let expected = hash_reader(&mut i.to_string().as_bytes(), HashAlgorithm::Sha256)?;
+            assert_eq!(result?, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_hash_check() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let good = dir.path().join("good.txt");
+        let bad = dir.path().join("bad.txt");
+        std::fs::write(&good, "abc")?;
+        std::fs::write(&bad, "tampered")?;
+        let sums = dir.path().join("SHA256SUMS");
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        std::fs::write(
+            &sums,
+            format!(
+                "{abc}  {}\n{abc} *{}\n{abc}  {}\n",
+                good.display(),
+                bad.display(),
+                dir.path().join("missing.txt").display()
+            ),
+        )?;
+
+        let results = process_hash_check(sums.to_str().unwrap(), Some(HashAlgorithm::Sha256), 2)?;
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[0].1, Ok(true)));
+        assert!(matches!(results[1].1, Ok(false)));
+        assert!(results[2].1.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_hash_check_infers_algorithm() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("abc.txt");
+        std::fs::write(&file, "abc")?;
+        let sha256 = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let md5 = "900150983cd24fb0d6963f7d28e17f72";
+        let write_sums = |name: &str, digest: &str| -> Result<String> {
+            let path = dir.path().join(name);
+            std::fs::write(&path, format!("{digest}  {}\n", file.display()))?;
+            Ok(path.to_string_lossy().into_owned())
+        };
+
+        // 文件名决定算法
+        for name in [
+            "SHA256SUMS",
+            "abc.txt.sha256",
+            "release-sha256sums.txt",
+            "sha256sum.txt",
+        ] {
+            let results = process_hash_check(&write_sums(name, sha256)?, None, 1)?;
+            assert!(matches!(results[0].1, Ok(true)), "{name}");
+        }
+        // 长度唯一时按长度推断
+        let results = process_hash_check(&write_sums("sums.txt", md5)?, None, 1)?;
+        assert!(matches!(results[0].1, Ok(true)));
+        // 64 个字符可能是 blake3 / sha256 / sha3-256，必须显式指定；名字里碰巧含有 b3 也不算
+        for name in ["sums.txt", "app-1.0b3.sums", "dumb3rd.txt"] {
+            assert!(
+                process_hash_check(&write_sums(name, sha256)?, None, 1).is_err(),
+                "{name}"
+            );
+        }
+        for name in ["b3sums", "abc.b3", "BLAKE3SUMS.txt"] {
+            let blake3 = hash_reader(&mut &b"abc"[..], HashAlgorithm::Blake3)?;
+            let results = process_hash_check(&write_sums(name, &blake3)?, None, 1)?;
+            assert!(matches!(results[0].1, Ok(true)), "{name}");
+        }
+
+        // 摘要长度与算法不符是格式错误，而不是 FAILED
+        let err = process_hash_check(&write_sums("MD5SUMS", sha256)?, None, 1)
+            .expect_err("length mismatch");
+        assert!(err.to_string().contains("摘要长度"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_repeated_stdin_is_rejected() -> Result<()> {
+        // 多个 `-` 时都不读取标准输入，避免并发读取得到交错的数据
+        let inputs = vec!["-".to_string(), "-".to_string()];
+        assert!(process_hash(&inputs, HashAlgorithm::Sha256, 2)
+            .iter()
+            .all(|result| result.is_err()));
+
+        let dir = tempfile::tempdir()?;
+        let sums = dir.path().join("SHA256SUMS");
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        std::fs::write(&sums, format!("{abc}  -\n{abc}  -\n"))?;
+        assert!(process_hash_check(sums.to_str().unwrap(), None, 2).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_sums_rejects_garbage() {
+        assert!(parse_sums("not-a-digest  file.txt").is_err());
+        assert!(parse_sums("abcdef").is_err());
+    }
+}
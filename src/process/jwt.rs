@@ -1,11 +1,14 @@
-use crate::cli::{JwtSignOpts, JwtVerifyOpts};
+use crate::cli::{JwtSecretOpts, JwtSignOpts, JwtVerifyOpts};
+use crate::SecretSource;
 use anyhow::{ensure, Context, Result};
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
 use std::{
     env,
     time::{SystemTime, UNIX_EPOCH},
 };
+use zeroize::Zeroizing;
 
 // JWT Claims structure
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,26 +21,30 @@ struct Claims {
 
 const MIN_SECRET_LEN: usize = 32;
 
-fn resolve_secret(secret: &Option<String>) -> Result<Vec<u8>> {
-    if let Some(ref value) = secret {
-        ensure!(!value.is_empty(), "Secret cannot be empty");
-        ensure!(
-            value.len() >= MIN_SECRET_LEN,
-            "Secret must be at least {} bytes for HS256",
-            MIN_SECRET_LEN
-        );
-        return Ok(value.as_bytes().to_vec());
-    }
-
-    let env_secret = env::var("JWT_SECRET")
-        .context("HS256 secret missing; supply --secret or set JWT_SECRET env var")?;
-    ensure!(!env_secret.is_empty(), "JWT_SECRET cannot be empty");
+/// 读取 HS256 共享密钥：显式来源优先，其次 JWT_SECRET，最后在终端提示输入；`confirm` 用于签发时二次确认。
+/// 返回的密钥释放时清零
+fn resolve_secret(opts: &JwtSecretOpts, confirm: bool) -> Result<Zeroizing<Vec<u8>>> {
+    let (secret, name) = match SecretSource::from(opts.clone()) {
+        SecretSource::Prompt => match env::var("JWT_SECRET") {
+            Ok(secret) => (SecretString::from(secret), "JWT_SECRET"),
+            Err(_) => (
+                SecretSource::Prompt
+                    .read("Enter HS256 secret: ", confirm)
+                    .context("HS256 secret missing; supply --secret-file, --secret-env, --secret-fd or set JWT_SECRET env var")?,
+                "Secret",
+            ),
+        },
+        source => (source.read("", false)?, "Secret"),
+    };
+    let secret = secret.expose_secret();
+    ensure!(!secret.is_empty(), "{} cannot be empty", name);
     ensure!(
-        env_secret.len() >= MIN_SECRET_LEN,
-        "JWT_SECRET must be at least {} bytes for HS256",
+        secret.len() >= MIN_SECRET_LEN,
+        "{} must be at least {} bytes for HS256",
+        name,
         MIN_SECRET_LEN
     );
-    Ok(env_secret.into_bytes())
+    Ok(Zeroizing::new(secret.as_bytes().to_vec()))
 }
 
 /// Parse duration string like "14d", "1h", "30m" into seconds
@@ -86,7 +93,7 @@ fn get_current_timestamp() -> Result<u64> {
 pub fn process_jwt_sign(opts: &JwtSignOpts) -> Result<String> {
     let now = get_current_timestamp()?;
     let exp_duration = parse_duration(&opts.exp)?;
-    let secret = resolve_secret(&opts.secret)?;
+    let secret = resolve_secret(&opts.secret, true)?;
 
     let claims = Claims {
         sub: opts.sub.clone(),
@@ -104,7 +111,7 @@ pub fn process_jwt_sign(opts: &JwtSignOpts) -> Result<String> {
 
 /// Verify a JWT token
 pub fn process_jwt_verify(opts: &JwtVerifyOpts) -> Result<bool> {
-    let secret = resolve_secret(&opts.secret)?;
+    let secret = resolve_secret(&opts.secret, false)?;
     let decoding_key = DecodingKey::from_secret(&secret);
     let mut validation = Validation::new(Algorithm::HS256);
     let aud_slice = [opts.aud.as_str()];
@@ -158,7 +165,10 @@ mod tests {
             sub: "acme".to_string(),
             aud: "device1".to_string(),
             exp: "1h".to_string(),
-            secret: Some(SECRET.to_string()),
+            secret: JwtSecretOpts {
+                secret: Some(SECRET.into()),
+                ..Default::default()
+            },
         };
 
         let token = process_jwt_sign(&sign_opts)?;
@@ -167,7 +177,10 @@ mod tests {
         let verify_opts = JwtVerifyOpts {
             token: token.clone(),
             aud: "device1".to_string(),
-            secret: Some(SECRET.to_string()),
+            secret: JwtSecretOpts {
+                secret: Some(SECRET.into()),
+                ..Default::default()
+            },
         };
 
         let is_valid = process_jwt_verify(&verify_opts)?;
@@ -180,7 +193,10 @@ mod tests {
         let verify_opts = JwtVerifyOpts {
             token: "invalid.jwt.token".to_string(),
             aud: "device1".to_string(),
-            secret: Some(SECRET.to_string()),
+            secret: JwtSecretOpts {
+                secret: Some(SECRET.into()),
+                ..Default::default()
+            },
         };
 
         let is_valid = process_jwt_verify(&verify_opts)?;
@@ -193,7 +209,10 @@ mod tests {
         let verify_opts = JwtVerifyOpts {
             token: "not-a-jwt-at-all".to_string(),
             aud: "device1".to_string(),
-            secret: Some(SECRET.to_string()),
+            secret: JwtSecretOpts {
+                secret: Some(SECRET.into()),
+                ..Default::default()
+            },
         };
 
         let is_valid = process_jwt_verify(&verify_opts)?;
@@ -203,8 +222,22 @@ mod tests {
 
     #[test]
     fn test_resolve_secret_requires_length() {
-        let short = Some("short".to_string());
-        assert!(resolve_secret(&short).is_err());
+        let short = JwtSecretOpts {
+            secret: Some("short".into()),
+            ..Default::default()
+        };
+        assert!(resolve_secret(&short, false).is_err());
+    }
+
+    #[test]
+    fn test_resolve_secret_from_named_env() -> Result<()> {
+        env::set_var("RST_TEST_JWT_SECRET", SECRET);
+        let opts = JwtSecretOpts {
+            secret_env: Some("RST_TEST_JWT_SECRET".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_secret(&opts, true)?.as_slice(), SECRET.as_bytes());
+        Ok(())
     }
 
     #[test]
@@ -212,8 +245,8 @@ mod tests {
         let key = "averylongandsecurejwtsecretstring!!!";
         let prev = env::var("JWT_SECRET").ok();
         env::set_var("JWT_SECRET", key);
-        let resolved = resolve_secret(&None)?;
-        assert_eq!(resolved, key.as_bytes());
+        let resolved = resolve_secret(&JwtSecretOpts::default(), false)?;
+        assert_eq!(resolved.as_slice(), key.as_bytes());
         if let Some(prev) = prev {
             env::set_var("JWT_SECRET", prev);
         } else {
@@ -0,0 +1,330 @@
+//! 口令保护的密钥文件：Argon2id 派生密钥 + ChaCha20-Poly1305 加密原始密钥文件内容
+//!
+//! 文件为 PEM 风格的文本，正文为 Base64(头部 || 密文)，头部同时作为 AEAD 关联数据：
+//! `version(1) || m_cost(4, LE) || t_cost(4, LE) || p_cost(4, LE) || salt(16) || nonce(12)`
+
+use super::container::KdfParams;
+use anyhow::{bail, ensure, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use secrecy::{ExposeSecret, SecretString};
+use std::env;
+use zeroize::Zeroizing;
+
+const BEGIN: &str = "-----BEGIN RST ENCRYPTED KEY-----";
+const END: &str = "-----END RST ENCRYPTED KEY-----";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 1 + 12 + 16 + 12;
+const M_COST: u32 = 65536;
+const T_COST: u32 = 3;
+const P_COST: u32 = 1;
+
+/// 用于非交互场景（脚本、CI）的口令环境变量
+pub const PASSPHRASE_ENV: &str = "RST_PASSPHRASE";
+/// `rst text key passwd` 的新口令环境变量
+pub const NEW_PASSPHRASE_ENV: &str = "RST_NEW_PASSPHRASE";
+
+pub fn is_protected(data: &[u8]) -> bool {
+    std::str::from_utf8(data).is_ok_and(|text| text.trim_start().starts_with(BEGIN))
+}
+
+pub fn protect_key(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    protect_key_with(data, passphrase, M_COST, T_COST, P_COST)
+}
+
+pub(crate) fn protect_key_with(
+    data: &[u8],
+    passphrase: &str,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<Vec<u8>> {
+    ensure!(!passphrase.is_empty(), "Passphrase cannot be empty");
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    rand::rng().fill_bytes(&mut salt);
+    rand::rng().fill_bytes(&mut nonce);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.push(VERSION);
+    for value in [m_cost, t_cost, p_cost] {
+        header.extend_from_slice(&value.to_le_bytes());
+    }
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+
+    let cipher = derive_cipher(passphrase, &salt, m_cost, t_cost, p_cost)?;
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: data,
+                aad: &header,
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("Key encryption failed: {e}"))?;
+
+    header.extend_from_slice(&ciphertext);
+    let body = STANDARD.encode(header);
+    let mut out = format!("{}\n", BEGIN);
+    for line in body.as_bytes().chunks(64) {
+        out.push_str(std::str::from_utf8(line).expect("base64 is ascii"));
+        out.push('\n');
+    }
+    out.push_str(END);
+    out.push('\n');
+    Ok(out.into_bytes())
+}
+
+pub fn unprotect_key(data: &[u8], passphrase: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let text = std::str::from_utf8(data).context("Protected key file must be text")?;
+    let body: String = text
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != BEGIN)
+        .skip(1)
+        .take_while(|line| *line != END)
+        .collect();
+    let data = STANDARD
+        .decode(body)
+        .context("Protected key file is not valid Base64")?;
+    ensure!(data.len() > HEADER_LEN, "Protected key file is truncated");
+    let (header, ciphertext) = data.split_at(HEADER_LEN);
+    if header[0] != VERSION {
+        bail!("Unsupported protected key version {}", header[0]);
+    }
+    let cost = |i: usize| u32::from_le_bytes(header[1 + i * 4..5 + i * 4].try_into().unwrap());
+    let params = KdfParams {
+        memory: cost(0),
+        iterations: cost(1),
+        parallelism: cost(2),
+    };
+    // 头部参数在认证之前就要使用，必须先限制范围
+    params.check_limits()?;
+    let (salt, nonce) = header[13..].split_at(16);
+
+    let cipher = derive_cipher(
+        passphrase,
+        salt,
+        params.memory,
+        params.iterations,
+        params.parallelism,
+    )?;
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| anyhow::anyhow!("Wrong passphrase or corrupted key file"))
+}
+
+/// 若密钥文件受口令保护则解密，口令优先取自 `RST_PASSPHRASE`，否则在终端提示输入
+pub fn unlock_key(data: Vec<u8>) -> Result<Zeroizing<Vec<u8>>> {
+    if !is_protected(&data) {
+        return Ok(Zeroizing::new(data));
+    }
+    let passphrase = read_passphrase(PASSPHRASE_ENV, "Enter passphrase for key: ", false)?;
+    unprotect_key(&data, &passphrase)
+}
+
+/// 读取口令：环境变量优先，否则从终端读取（不回显），`confirm` 为 true 时要求输入两次
+pub fn read_passphrase(env_var: &str, prompt: &str, confirm: bool) -> Result<Zeroizing<String>> {
+    if let Ok(passphrase) = env::var(env_var) {
+        return Ok(Zeroizing::new(passphrase));
+    }
+    prompt_passphrase(prompt, confirm)
+        .with_context(|| format!("Failed to read passphrase; set {} instead", env_var))
+}
+
+/// 口令或共享密钥的来源；命令行参数会留在 shell 历史与 `ps` 输出中，应优先使用其余方式
+#[derive(Debug, Clone)]
+pub enum SecretSource {
+    Arg(SecretString),
+    /// 取文件第一行
+    File(String),
+    /// 环境变量名
+    Env(String),
+    /// 已打开的文件描述符（如 `3<secret.txt`），取第一行
+    Fd(i32),
+    /// 在终端提示输入（不回显）
+    Prompt,
+}
+
+impl SecretSource {
+    /// 按命令行选项选择来源，均未指定时为 `Prompt`
+    pub fn from_options(
+        value: Option<SecretString>,
+        file: Option<String>,
+        env_var: Option<String>,
+        fd: Option<i32>,
+    ) -> Self {
+        match (value, file, env_var, fd) {
+            (Some(value), ..) => SecretSource::Arg(value),
+            (_, Some(path), ..) => SecretSource::File(path),
+            (_, _, Some(name), _) => SecretSource::Env(name),
+            (.., Some(fd)) => SecretSource::Fd(fd),
+            _ => SecretSource::Prompt,
+        }
+    }
+
+    /// 读取口令，拒绝空值；`confirm` 仅对终端提示生效，要求输入两次
+    pub fn read(&self, prompt: &str, confirm: bool) -> Result<SecretString> {
+        let secret = match self {
+            SecretSource::Arg(value) => value.expose_secret().to_string(),
+            SecretSource::File(path) => first_line(Zeroizing::new(
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read passphrase file {}", path))?,
+            )),
+            SecretSource::Env(name) => env::var(name)
+                .with_context(|| format!("Environment variable {} is not set", name))?,
+            SecretSource::Fd(fd) => first_line(Zeroizing::new(read_fd(*fd)?)),
+            SecretSource::Prompt => std::mem::take(&mut *prompt_passphrase(prompt, confirm).context(
+                "No terminal to prompt on; pass the passphrase with a file, environment variable or file descriptor",
+            )?),
+        };
+        let secret = SecretString::from(secret);
+        ensure!(
+            !secret.expose_secret().is_empty(),
+            "Passphrase cannot be empty"
+        );
+        Ok(secret)
+    }
+}
+
+fn prompt_passphrase(prompt: &str, confirm: bool) -> Result<Zeroizing<String>> {
+    let passphrase = Zeroizing::new(rpassword::prompt_password(prompt)?);
+    if confirm {
+        let again = Zeroizing::new(rpassword::prompt_password("Confirm passphrase: ")?);
+        ensure!(passphrase == again, "Passphrases do not match");
+    }
+    Ok(passphrase)
+}
+
+fn first_line(text: Zeroizing<String>) -> String {
+    text.lines().next().unwrap_or_default().to_string()
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<String> {
+    std::fs::read_to_string(format!("/dev/fd/{}", fd))
+        .with_context(|| format!("Failed to read passphrase from file descriptor {}", fd))
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<String> {
+    bail!("Reading the passphrase from a file descriptor is only supported on Unix")
+}
+
+fn derive_cipher(
+    passphrase: &str,
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<ChaCha20Poly1305> {
+    let key = argon2id_key(passphrase.as_bytes(), salt, m_cost, t_cost, p_cost)?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_slice())))
+}
+
+/// Argon2id（v0x13）派生 32 字节密钥，`m_cost` 单位为 KiB；返回的密钥释放时清零
+pub(crate) fn argon2id_key(
+    passphrase: &[u8],
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<Zeroizing<[u8; 32]>> {
+    let params = Params::new(m_cost, t_cost, p_cost, Some(32))
+        .map_err(|e| anyhow::anyhow!("Invalid Argon2 parameters: {e}"))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase, salt, key.as_mut_slice())
+        .map_err(|e| anyhow::anyhow!("Argon2 derivation failed: {e}"))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protect_roundtrip() -> Result<()> {
+        let key = std::fs::read("fixtures/ed25519.sk")?;
+        let protected = protect_key_with(&key, "correct horse", 1024, 1, 1)?;
+        assert!(is_protected(&protected));
+        assert!(!is_protected(&key));
+        assert_eq!(*unprotect_key(&protected, "correct horse")?, key);
+        assert!(unprotect_key(&protected, "wrong horse").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_protect_detects_tampered_header() -> Result<()> {
+        let protected = protect_key_with(b"secret", "pass", 1024, 1, 1)?;
+        let text = String::from_utf8(protected)?;
+        let body: String = text.lines().skip(1).take_while(|l| *l != END).collect();
+        let mut data = STANDARD.decode(body)?;
+        // 头部参与认证，修改 KDF 参数会导致解密失败
+        data[1] ^= 1;
+        let tampered = format!("{}\n{}\n{}\n", BEGIN, STANDARD.encode(data), END);
+        assert!(unprotect_key(tampered.as_bytes(), "pass").is_err());
+        assert!(protect_key_with(b"secret", "", 1024, 1, 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unprotect_rejects_oversized_kdf_costs() -> Result<()> {
+        let protected = String::from_utf8(protect_key_with(b"secret", "pass", 1024, 1, 1)?)?;
+        let body: String = protected
+            .lines()
+            .skip(1)
+            .take_while(|l| *l != END)
+            .collect();
+        let data = STANDARD.decode(body)?;
+        // m_cost、t_cost、p_cost 依次位于版本字节之后
+        for offset in [1, 5, 9] {
+            let mut oversized = data.clone();
+            oversized[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            let tampered = format!("{}\n{}\n{}\n", BEGIN, STANDARD.encode(oversized), END);
+            let err = unprotect_key(tampered.as_bytes(), "pass").unwrap_err();
+            assert!(err.to_string().contains("exceeds"), "{err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_secret_sources() -> Result<()> {
+        let read = |source: SecretSource| -> Result<String> {
+            Ok(source.read("", false)?.expose_secret().to_string())
+        };
+        let mut file = tempfile::NamedTempFile::new()?;
+        std::io::Write::write_all(&mut file, b"from file\nignored\n")?;
+        let path = file.path().to_string_lossy().to_string();
+        assert_eq!(read(SecretSource::File(path.clone()))?, "from file");
+        #[cfg(unix)]
+        {
+            use std::os::fd::AsRawFd;
+            let fd = std::fs::File::open(&path)?;
+            assert_eq!(read(SecretSource::Fd(fd.as_raw_fd()))?, "from file");
+        }
+
+        env::set_var("RST_TEST_SECRET_SOURCE", "from env");
+        let source =
+            SecretSource::from_options(None, None, Some("RST_TEST_SECRET_SOURCE".into()), None);
+        assert_eq!(read(source)?, "from env");
+        assert!(read(SecretSource::Env("RST_TEST_SECRET_UNSET".into())).is_err());
+        assert!(read(SecretSource::Arg(SecretString::default())).is_err());
+        assert!(matches!(
+            SecretSource::from_options(None, None, None, None),
+            SecretSource::Prompt
+        ));
+        Ok(())
+    }
+}
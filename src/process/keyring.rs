@@ -0,0 +1,248 @@
+//! 本地密钥环：密钥文件按名称保存在配置目录中，索引记录算法、类型与 BLAKE3 指纹
+//!
+//! 目录优先级：`RST_KEYRING_DIR` > `$XDG_CONFIG_HOME/rst/keys` > `~/.config/rst/keys`
+
+use crate::{process_text_key_fingerprint, unlock_key, write_secret_file, TextSignFormat};
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const KEYRING_DIR_ENV: &str = "RST_KEYRING_DIR";
+const INDEX_FILE: &str = "keyring.json";
+/// 按指纹前缀查找时要求的最短长度，避免误匹配
+const MIN_FINGERPRINT_PREFIX: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyEntry {
+    pub name: String,
+    /// `blake3`、`hmac`（SHA-256/512 共用）、`ed25519`（Ed25519ph 共用）、`ecdsa-p256` 或 `ecdsa-secp256k1`
+    pub algorithm: String,
+    pub secret: bool,
+    pub fingerprint: String,
+    /// 添加时间（Unix 秒）
+    pub added: u64,
+}
+
+impl KeyEntry {
+    /// 检查密钥能否用于指定的签名格式
+    pub fn supports(&self, format: TextSignFormat) -> bool {
+        matches!(
+            (self.algorithm.as_str(), format),
+            ("blake3", TextSignFormat::Blake3)
+                | (
+                    "hmac",
+                    TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512
+                )
+                | (
+                    "ed25519",
+                    TextSignFormat::Ed25519 | TextSignFormat::Ed25519ph
+                )
+                | ("ecdsa-p256", TextSignFormat::EcdsaP256)
+                | ("ecdsa-secp256k1", TextSignFormat::EcdsaSecp256k1)
+        )
+    }
+}
+
+pub struct Keyring {
+    dir: PathBuf,
+}
+
+impl Keyring {
+    pub fn open() -> Result<Self> {
+        let dir = match env::var_os(KEYRING_DIR_ENV) {
+            Some(dir) => PathBuf::from(dir),
+            None => env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+                .context("Cannot locate the config directory; set RST_KEYRING_DIR")?
+                .join("rst")
+                .join("keys"),
+        };
+        Ok(Self::at(dir))
+    }
+
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn list(&self) -> Result<Vec<KeyEntry>> {
+        let path = self.dir.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Corrupted keyring index '{}'", path.display()))
+    }
+
+    /// 添加密钥；受口令保护的密钥原样保存，仅在计算指纹时解锁
+    pub fn add(
+        &self,
+        name: &str,
+        data: &[u8],
+        format: TextSignFormat,
+        secret: bool,
+    ) -> Result<KeyEntry> {
+        validate_name(name)?;
+        let algorithm = match format {
+            TextSignFormat::Blake3 => "blake3",
+            TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512 => "hmac",
+            TextSignFormat::Ed25519 | TextSignFormat::Ed25519ph => "ed25519",
+            TextSignFormat::EcdsaP256 => "ecdsa-p256",
+            TextSignFormat::EcdsaSecp256k1 => "ecdsa-secp256k1",
+            TextSignFormat::Minisign => bail!("minisign keys cannot be stored in the keyring"),
+        };
+        let mut entries = self.list()?;
+        ensure!(
+            entries.iter().all(|entry| entry.name != name),
+            "A key named '{}' already exists",
+            name
+        );
+
+        let fingerprint =
+            process_text_key_fingerprint(&unlock_key(data.to_vec())?, format, secret)?;
+        let entry = KeyEntry {
+            name: name.to_string(),
+            algorithm: algorithm.to_string(),
+            secret,
+            fingerprint,
+            added: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        write_secret_file(self.key_path(name), data)?;
+        entries.push(entry.clone());
+        self.save(&entries)?;
+        Ok(entry)
+    }
+
+    pub fn remove(&self, query: &str) -> Result<KeyEntry> {
+        let entry = self.find(query, None)?;
+        let mut entries = self.list()?;
+        entries.retain(|e| e.name != entry.name);
+        self.save(&entries)?;
+        fs::remove_file(self.key_path(&entry.name))?;
+        Ok(entry)
+    }
+
+    /// 按名称或指纹前缀查找；同一指纹同时存在私钥与公钥时按 `prefer_secret` 选择
+    pub fn find(&self, query: &str, prefer_secret: Option<bool>) -> Result<KeyEntry> {
+        let entries = self.list()?;
+        if let Some(entry) = entries.iter().find(|entry| entry.name == query) {
+            return Ok(entry.clone());
+        }
+
+        ensure!(
+            query.len() >= MIN_FINGERPRINT_PREFIX,
+            "No key named '{}' in the keyring",
+            query
+        );
+        let prefix = query.to_ascii_lowercase();
+        let mut matches: Vec<&KeyEntry> = entries
+            .iter()
+            .filter(|entry| entry.fingerprint.starts_with(&prefix))
+            .collect();
+        if matches.len() > 1 {
+            if let Some(secret) = prefer_secret {
+                if matches.iter().any(|entry| entry.secret == secret) {
+                    matches.retain(|entry| entry.secret == secret);
+                }
+            }
+        }
+        match matches.as_slice() {
+            [] => bail!("No key named or fingerprinted '{}' in the keyring", query),
+            [entry] => Ok((*entry).clone()),
+            _ => bail!(
+                "'{}' matches several keys ({}); use the key name instead",
+                query,
+                matches
+                    .iter()
+                    .map(|entry| entry.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// 读取密钥文件内容（可能仍受口令保护）
+    pub fn load(&self, entry: &KeyEntry) -> Result<Vec<u8>> {
+        fs::read(self.key_path(&entry.name))
+            .with_context(|| format!("Key file for '{}' is missing", entry.name))
+    }
+
+    fn key_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.key", name))
+    }
+
+    fn save(&self, entries: &[KeyEntry]) -> Result<()> {
+        let json = serde_json::to_string_pretty(entries)?;
+        fs::write(self.dir.join(INDEX_FILE), json + "\n")?;
+        Ok(())
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    ensure!(
+        !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@')),
+        "Invalid key name '{}'; use letters, digits, '-', '_', '.' or '@'",
+        name
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyring_add_find_remove() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let keyring = Keyring::at(dir.path());
+        assert!(keyring.list()?.is_empty());
+
+        let sk = fs::read("fixtures/ed25519.sk")?;
+        let pk = fs::read("fixtures/ed25519.pk")?;
+        let secret = keyring.add("release", &sk, TextSignFormat::Ed25519, true)?;
+        let public = keyring.add("release.pub", &pk, TextSignFormat::Ed25519, false)?;
+        // 同一密钥对的私钥与公钥指纹相同
+        assert_eq!(secret.fingerprint, public.fingerprint);
+        assert!(keyring
+            .add("release", &sk, TextSignFormat::Ed25519, true)
+            .is_err());
+
+        assert_eq!(keyring.find("release", None)?, secret);
+        let prefix = &secret.fingerprint[..6];
+        assert_eq!(keyring.find(prefix, Some(true))?, secret);
+        assert_eq!(keyring.find(prefix, Some(false))?, public);
+        assert!(keyring.find(prefix, None).is_err());
+        assert_eq!(keyring.load(&public)?, pk);
+
+        keyring.remove("release.pub")?;
+        assert_eq!(keyring.find(prefix, Some(false))?, secret);
+        assert_eq!(keyring.list()?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyring_rejects_bad_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = Keyring::at(dir.path());
+        for name in ["", "../escape", "a/b", ".hidden"] {
+            assert!(keyring
+                .add(name, &[1u8; 32], TextSignFormat::Blake3, true)
+                .is_err());
+        }
+    }
+}
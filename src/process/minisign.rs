@@ -0,0 +1,432 @@
+//! minisign 兼容的密钥与签名格式，规范见 <https://jedisct1.github.io/minisign/>
+//!
+//! 签名文件（`.minisig`）共四行：
+//! `untrusted comment`、Base64(算法 || key id || 签名)、`trusted comment`、
+//! Base64(对 签名 || trusted comment 的全局签名)。
+//! 算法 `ED` 对输入的 BLAKE2b-512 摘要签名（默认，流式），`Ed` 为旧版直接签名原文。
+
+use super::key_protect::{read_passphrase, PASSPHRASE_ENV};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use blake2::{digest::consts::U32, Blake2b, Blake2b512, Digest};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+use std::io::{self, Read};
+use zeroize::Zeroizing;
+
+const SIG_ALG: &[u8; 2] = b"Ed";
+const SIG_ALG_HASHED: &[u8; 2] = b"ED";
+const KDF_NONE: &[u8; 2] = &[0, 0];
+const KDF_SCRYPT: &[u8; 2] = b"Sc";
+const CHK_ALG: &[u8; 2] = b"B2";
+const UNTRUSTED_PREFIX: &str = "untrusted comment: ";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+/// sig_alg(2) + kdf_alg(2) + chk_alg(2) + salt(32) + opslimit(8) + memlimit(8) + key_id(8) + sk(64) + chk(32)
+const SECRET_KEY_LEN: usize = 158;
+const PUBLIC_KEY_LEN: usize = 42;
+const SIGNATURE_LEN: usize = 74;
+/// 加密部分：key_id(8) + sk(64) + chk(32)
+const ENCRYPTED_RANGE: std::ops::Range<usize> = 54..SECRET_KEY_LEN;
+/// minisign 默认参数即为 libsodium 的 sensitive 档位（opslimit 2^25、memlimit 1 GiB），超出视为恶意文件
+const MAX_OPSLIMIT: u64 = 1 << 25;
+const MAX_MEMLIMIT: u64 = 1 << 30;
+
+pub struct MinisignSecretKey {
+    key_id: [u8; 8],
+    key: SigningKey,
+}
+
+pub struct MinisignPublicKey {
+    key_id: [u8; 8],
+    key: VerifyingKey,
+}
+
+/// 校验通过的签名所携带的 trusted comment
+pub struct MinisignVerified {
+    pub trusted_comment: String,
+}
+
+impl MinisignSecretKey {
+    pub fn generate() -> Self {
+        let mut seed = Zeroizing::new([0u8; 32]);
+        let mut key_id = [0u8; 8];
+        rand::rng().fill_bytes(seed.as_mut_slice());
+        rand::rng().fill_bytes(&mut key_id);
+        Self {
+            key_id,
+            key: SigningKey::from_bytes(&seed),
+        }
+    }
+
+    /// 解析 minisign 密钥文件；加密的密钥（`minisign -G` 默认）从 `RST_PASSPHRASE` 读取口令，否则在终端提示输入
+    pub fn decode(content: &str) -> Result<Self> {
+        Self::decode_with(content, || {
+            read_passphrase(PASSPHRASE_ENV, "Enter minisign key passphrase: ", false)
+        })
+    }
+
+    /// 仅在密钥加密时才调用 `passphrase` 获取口令
+    fn decode_with(
+        content: &str,
+        passphrase: impl FnOnce() -> Result<Zeroizing<String>>,
+    ) -> Result<Self> {
+        let mut bin = Zeroizing::new(decode_key_line(content)?);
+        ensure!(
+            bin.len() == SECRET_KEY_LEN,
+            "Invalid minisign secret key length"
+        );
+        ensure!(&bin[..2] == SIG_ALG, "Unsupported minisign key algorithm");
+        let encrypted = match &bin[2..4] {
+            kdf if kdf == KDF_NONE => false,
+            kdf if kdf == KDF_SCRYPT => true,
+            _ => bail!("Unknown minisign key derivation algorithm"),
+        };
+        ensure!(
+            &bin[4..6] == CHK_ALG,
+            "Unsupported minisign checksum algorithm"
+        );
+        if encrypted {
+            let opslimit = u64::from_le_bytes(bin[38..46].try_into()?);
+            let memlimit = u64::from_le_bytes(bin[46..54].try_into()?);
+            let params = scrypt_params(opslimit, memlimit)?;
+            let passphrase = passphrase()?;
+            let mut stream = Zeroizing::new([0u8; SECRET_KEY_LEN - ENCRYPTED_RANGE.start]);
+            scrypt::scrypt(passphrase.as_bytes(), &bin[6..38], &params, &mut stream[..])
+                .map_err(|e| anyhow!("scrypt failed: {e}"))?;
+            for (byte, mask) in bin[ENCRYPTED_RANGE].iter_mut().zip(stream.iter()) {
+                *byte ^= mask;
+            }
+        }
+
+        let key_id: [u8; 8] = bin[54..62].try_into()?;
+        let sk: &[u8] = &bin[62..126];
+        if checksum(&key_id, sk) != bin[126..158] {
+            match encrypted {
+                true => bail!("Wrong passphrase or corrupted minisign secret key"),
+                false => bail!("minisign secret key checksum mismatch"),
+            }
+        }
+        let key = SigningKey::from_bytes(sk[..32].try_into()?);
+        ensure!(
+            key.verifying_key().as_bytes() == &sk[32..],
+            "minisign secret key is corrupted"
+        );
+        Ok(Self { key_id, key })
+    }
+
+    pub fn encode(&self) -> String {
+        let mut sk = Zeroizing::new([0u8; 64]);
+        sk[..32].copy_from_slice(self.key.as_bytes());
+        sk[32..].copy_from_slice(self.key.verifying_key().as_bytes());
+
+        let mut bin = Zeroizing::new(Vec::with_capacity(SECRET_KEY_LEN));
+        bin.extend_from_slice(SIG_ALG);
+        bin.extend_from_slice(KDF_NONE);
+        bin.extend_from_slice(CHK_ALG);
+        // 未加密时 salt 与 scrypt 参数不参与运算，置零即可
+        bin.extend_from_slice(&[0u8; 48]);
+        bin.extend_from_slice(&self.key_id);
+        bin.extend_from_slice(sk.as_slice());
+        bin.extend_from_slice(&checksum(&self.key_id, sk.as_slice()));
+        format!(
+            "{}minisign secret key {}\n{}\n",
+            UNTRUSTED_PREFIX,
+            key_id_hex(&self.key_id),
+            STANDARD.encode(bin.as_slice())
+        )
+    }
+
+    pub fn public_key(&self) -> MinisignPublicKey {
+        MinisignPublicKey {
+            key_id: self.key_id,
+            key: self.key.verifying_key(),
+        }
+    }
+
+    /// 生成 `.minisig` 内容；`prehash` 为 false 时生成旧版 `Ed` 签名（需读取全部输入）
+    pub fn sign(
+        &self,
+        reader: &mut dyn Read,
+        trusted_comment: &str,
+        prehash: bool,
+    ) -> Result<String> {
+        ensure!(
+            !trusted_comment.contains(['\r', '\n']),
+            "Trusted comment must be a single line"
+        );
+        let (alg, signature) = if prehash {
+            (SIG_ALG_HASHED, self.key.sign(&blake2b_512(reader)?))
+        } else {
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf)?;
+            (SIG_ALG, self.key.sign(&buf))
+        };
+
+        let mut bin = Vec::with_capacity(SIGNATURE_LEN);
+        bin.extend_from_slice(alg);
+        bin.extend_from_slice(&self.key_id);
+        bin.extend_from_slice(&signature.to_bytes());
+
+        let mut global = signature.to_bytes().to_vec();
+        global.extend_from_slice(trusted_comment.as_bytes());
+        let global = self.key.sign(&global);
+
+        Ok(format!(
+            "{}signature from rst minisign secret key\n{}\n{}{}\n{}\n",
+            UNTRUSTED_PREFIX,
+            STANDARD.encode(bin),
+            TRUSTED_PREFIX,
+            trusted_comment,
+            STANDARD.encode(global.to_bytes())
+        ))
+    }
+}
+
+impl MinisignPublicKey {
+    /// 接受完整的 `.pub` 文件，也接受单独的 Base64 公钥行
+    pub fn decode(content: &str) -> Result<Self> {
+        let bin = decode_key_line(content)?;
+        ensure!(
+            bin.len() == PUBLIC_KEY_LEN,
+            "Invalid minisign public key length"
+        );
+        ensure!(&bin[..2] == SIG_ALG, "Unsupported minisign key algorithm");
+        Ok(Self {
+            key_id: bin[2..10].try_into()?,
+            key: VerifyingKey::from_bytes(bin[10..42].try_into()?)?,
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        let mut bin = Vec::with_capacity(PUBLIC_KEY_LEN);
+        bin.extend_from_slice(SIG_ALG);
+        bin.extend_from_slice(&self.key_id);
+        bin.extend_from_slice(self.key.as_bytes());
+        format!(
+            "{}minisign public key {}\n{}\n",
+            UNTRUSTED_PREFIX,
+            key_id_hex(&self.key_id),
+            STANDARD.encode(bin)
+        )
+    }
+
+    /// 校验 `.minisig`；签名或 trusted comment 被篡改时返回 `None`
+    pub fn verify(&self, reader: &mut dyn Read, minisig: &str) -> Result<Option<MinisignVerified>> {
+        let mut lines = minisig.lines().map(|line| line.trim_end_matches('\r'));
+        let (Some(untrusted), Some(sig_line), Some(trusted), Some(global_line)) =
+            (lines.next(), lines.next(), lines.next(), lines.next())
+        else {
+            bail!("Invalid minisign signature: expected 4 lines");
+        };
+        ensure!(
+            untrusted.starts_with(UNTRUSTED_PREFIX),
+            "Invalid minisign signature: missing untrusted comment"
+        );
+        let trusted_comment = trusted
+            .strip_prefix(TRUSTED_PREFIX)
+            .context("Invalid minisign signature: missing trusted comment")?;
+
+        let bin = STANDARD.decode(sig_line.trim())?;
+        ensure!(
+            bin.len() == SIGNATURE_LEN,
+            "Invalid minisign signature length"
+        );
+        let key_id: [u8; 8] = bin[2..10].try_into()?;
+        ensure!(
+            key_id == self.key_id,
+            "Signature key id {} does not match public key {}",
+            key_id_hex(&key_id),
+            key_id_hex(&self.key_id)
+        );
+        let signature = Signature::from_bytes(bin[10..].try_into()?);
+        let valid = match &bin[..2] {
+            alg if alg == SIG_ALG_HASHED => {
+                self.key.verify(&blake2b_512(reader)?, &signature).is_ok()
+            }
+            alg if alg == SIG_ALG => {
+                let mut buf = Vec::new();
+                reader.read_to_end(&mut buf)?;
+                self.key.verify(&buf, &signature).is_ok()
+            }
+            _ => bail!("Unsupported minisign signature algorithm"),
+        };
+        if !valid {
+            return Ok(None);
+        }
+
+        let global = Signature::from_bytes(STANDARD.decode(global_line.trim())?[..].try_into()?);
+        let mut signed = signature.to_bytes().to_vec();
+        signed.extend_from_slice(trusted_comment.as_bytes());
+        if self.key.verify(&signed, &global).is_err() {
+            return Ok(None);
+        }
+        Ok(Some(MinisignVerified {
+            trusted_comment: trusted_comment.to_string(),
+        }))
+    }
+}
+
+/// 跳过 `untrusted comment` 行，解码第一行 Base64 内容
+fn decode_key_line(content: &str) -> Result<Vec<u8>> {
+    let line = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED_PREFIX))
+        .context("minisign key file is empty")?;
+    STANDARD
+        .decode(line)
+        .context("minisign key is not valid Base64")
+}
+
+/// 按 libsodium `crypto_pwhash_scryptsalsa208sha256` 的 pickparams 规则由 opslimit / memlimit 推导 scrypt 参数
+fn scrypt_params(opslimit: u64, memlimit: u64) -> Result<scrypt::Params> {
+    ensure!(
+        opslimit <= MAX_OPSLIMIT && memlimit <= MAX_MEMLIMIT,
+        "minisign secret key scrypt parameters exceed the supported limits"
+    );
+    let opslimit = opslimit.max(32768);
+    let r: u32 = 8;
+    let (log_n, p) = if opslimit < memlimit / 32 {
+        (scrypt_log_n(opslimit / (r as u64 * 4)), 1)
+    } else {
+        let log_n = scrypt_log_n(memlimit / (r as u64 * 128));
+        let max_rp = ((opslimit / 4) >> log_n).min(0x3fff_ffff);
+        (log_n, max_rp as u32 / r)
+    };
+    // len 只用于 PHC 字符串，输出长度由 `scrypt::scrypt` 的缓冲区决定
+    scrypt::Params::new(log_n, r, p, scrypt::Params::RECOMMENDED_LEN)
+        .map_err(|e| anyhow!("Invalid minisign scrypt parameters: {e}"))
+}
+
+/// 取满足 2^log_n > max_n / 2 的最小 log_n
+fn scrypt_log_n(max_n: u64) -> u8 {
+    (1..63).find(|&n| (1u64 << n) > max_n / 2).unwrap_or(63)
+}
+
+fn checksum(key_id: &[u8; 8], sk: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(SIG_ALG);
+    hasher.update(key_id);
+    hasher.update(sk);
+    hasher.finalize().into()
+}
+
+fn blake2b_512(reader: &mut dyn Read) -> Result<[u8; 64]> {
+    let mut hasher = Blake2b512::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+/// minisign 以小端 u64 的大写十六进制显示 key id
+fn key_id_hex(key_id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 来自 minisign 生成的签名
+    const PUBLIC_KEY: &str = "untrusted comment: minisign public key E7620F1842B4E81F
+RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+    const LEGACY_SIG: &str = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==";
+    const HASHED_SIG: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==";
+
+    #[test]
+    fn test_verify_minisign_signatures() -> Result<()> {
+        let pk = MinisignPublicKey::decode(PUBLIC_KEY)?;
+        for sig in [LEGACY_SIG, HASHED_SIG] {
+            let verified = pk.verify(&mut &b"test"[..], sig)?.expect("valid signature");
+            assert!(verified.trusted_comment.starts_with("timestamp:"));
+            assert!(pk.verify(&mut &b"Test"[..], sig)?.is_none());
+        }
+        // 篡改 trusted comment 后全局签名失效
+        let tampered = HASHED_SIG.replace("file:test", "file:evil");
+        assert!(pk.verify(&mut &b"test"[..], &tampered)?.is_none());
+        Ok(())
+    }
+
+    // 按 minisign 的格式以 scrypt（opslimit 32768、memlimit 16 MiB）加密，口令为 "correct horse"
+    const ENCRYPTED_SECRET_KEY: &str = "untrusted comment: minisign encrypted secret key 285130B7606E1603
+RWRTY0IygBwCmXYTKnYWiHTZp+ID8e/lvrqqFqlSLtuctWx8seoAgAAAAAAAAAAAAAEAAAAAw5Qz5QVG7FJrCZW7KY+0QX33Zyc+iIIjZ+OwY9D0+makeATuKZHD61wXv6yEgXaon+busxnTt9J1OFmhrJr/xgmZWC6xsUdGapsqxOOj/ouzMCBspapHDI/cwt9DXCH5hCafVnPv/Ls=";
+    const ENCRYPTED_PUBLIC_KEY: &str = "untrusted comment: minisign public key 285130B7606E1603
+RWQDFm5gtzBRKIMkuqupXJkajWv0vnM6+aWxu0KEq67XMJ25vKXUtkvd";
+
+    #[test]
+    fn test_decode_encrypted_secret_key() -> Result<()> {
+        let sk = MinisignSecretKey::decode_with(ENCRYPTED_SECRET_KEY, || {
+            Ok(Zeroizing::new("correct horse".to_string()))
+        })?;
+        assert_eq!(sk.public_key().encode().trim(), ENCRYPTED_PUBLIC_KEY);
+
+        let err = MinisignSecretKey::decode_with(ENCRYPTED_SECRET_KEY, || {
+            Ok(Zeroizing::new("wrong".to_string()))
+        })
+        .err()
+        .expect("wrong passphrase");
+        assert!(err.to_string().contains("Wrong passphrase"));
+
+        // 未加密的密钥不会询问口令
+        let plain = MinisignSecretKey::generate().encode();
+        MinisignSecretKey::decode_with(&plain, || unreachable!())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_scrypt_params_match_libsodium() -> Result<()> {
+        // minisign 默认参数：N = 2^20, r = 8, p = 1
+        let params = scrypt_params(1 << 25, 1 << 30)?;
+        assert_eq!((params.log_n(), params.r(), params.p()), (20, 8, 1));
+        let params = scrypt_params(32768, 16 << 20)?;
+        assert_eq!((params.log_n(), params.r(), params.p()), (10, 8, 1));
+        assert!(scrypt_params(1 << 26, 1 << 30).is_err());
+        assert!(scrypt_params(1 << 25, 1 << 31).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_verifiable_by_minisign() -> Result<()> {
+        let sk = MinisignSecretKey::decode(&MinisignSecretKey::generate().encode())?;
+        let pk = MinisignPublicKey::decode(&sk.public_key().encode())?;
+        let data = vec![3u8; 100 * 1024];
+
+        for prehash in [true, false] {
+            let sig = sk.sign(&mut &data[..], "timestamp:0\tfile:data", prehash)?;
+            assert!(pk.verify(&mut &data[..], &sig)?.is_some());
+
+            let stock = minisign_verify::PublicKey::decode(pk.encode().trim())?;
+            let stock_sig = minisign_verify::Signature::decode(&sig)?;
+            stock.verify(&data, &stock_sig, !prehash)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_mismatch_and_encrypted_key() {
+        let other = MinisignSecretKey::generate().public_key();
+        assert!(other.verify(&mut &b"test"[..], HASHED_SIG).is_err());
+
+        let mut bin = STANDARD
+            .decode(
+                MinisignSecretKey::generate()
+                    .encode()
+                    .lines()
+                    .nth(1)
+                    .unwrap(),
+            )
+            .unwrap();
+        // 把未加密密钥标成 scrypt 加密后，解密结果无法通过校验和
+        bin[2..4].copy_from_slice(KDF_SCRYPT);
+        let encrypted = STANDARD.encode(bin);
+        assert!(
+            MinisignSecretKey::decode_with(&encrypted, || Ok(Zeroizing::new("pass".into())))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use crate::cli::{OtpAlgorithm, OtpGenerateOpts, OtpMode, OtpSecretNewOpts, OtpVerifyOpts};
+use anyhow::{ensure, Context, Result};
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use secrecy::{ExposeSecret, SecretString};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+/// Decode a base32 secret, tolerating lowercase, spaces and missing padding
+fn decode_secret(secret: &SecretString) -> Result<Zeroizing<Vec<u8>>> {
+    let normalized = Zeroizing::new(
+        secret
+            .expose_secret()
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+            .map(|c| c.to_ascii_uppercase())
+            .collect::<String>(),
+    );
+    ensure!(!normalized.is_empty(), "Secret cannot be empty");
+    BASE32_NOPAD
+        .decode(normalized.as_bytes())
+        .map(Zeroizing::new)
+        .context("Secret is not valid base32")
+}
+
+fn hmac_digest(algorithm: OtpAlgorithm, key: &[u8], msg: &[u8]) -> Result<Vec<u8>> {
+    fn mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], msg: &[u8]) -> Result<Vec<u8>> {
+        let mut mac = <M as Mac>::new_from_slice(key)
+            .map_err(|e| anyhow::anyhow!("Invalid HMAC key: {e}"))?;
+        mac.update(msg);
+        Ok(mac.finalize().into_bytes().to_vec())
+    }
+
+    match algorithm {
+        OtpAlgorithm::Sha1 => mac::<Hmac<Sha1>>(key, msg),
+        OtpAlgorithm::Sha256 => mac::<Hmac<Sha256>>(key, msg),
+        OtpAlgorithm::Sha512 => mac::<Hmac<Sha512>>(key, msg),
+    }
+}
+
+/// RFC 4226 HOTP with dynamic truncation
+fn hotp(key: &[u8], counter: u64, algorithm: OtpAlgorithm, digits: u32) -> Result<String> {
+    let digest = hmac_digest(algorithm, key, &counter.to_be_bytes())?;
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes(digest[offset..offset + 4].try_into()?) & 0x7fff_ffff;
+    let code = binary % 10u32.pow(digits);
+    Ok(format!("{:0width$}", code, width = digits as usize))
+}
+
+/// RFC 6238 time step for the given (or current) unix time
+fn time_step(time: Option<u64>, period: u64) -> Result<u64> {
+    let now = match time {
+        Some(time) => time,
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .map_err(|e| anyhow::anyhow!("Failed to get current time: {}", e))?,
+    };
+    Ok(now / period)
+}
+
+fn resolve_counter(
+    mode: OtpMode,
+    counter: Option<u64>,
+    time: Option<u64>,
+    period: u64,
+) -> Result<u64> {
+    match mode {
+        OtpMode::Totp => time_step(time, period),
+        OtpMode::Hotp => counter.context("HOTP requires --counter"),
+    }
+}
+
+/// Generate a TOTP or HOTP code
+pub fn process_otp_generate(opts: &OtpGenerateOpts) -> Result<String> {
+    let key = decode_secret(&opts.secret)?;
+    let counter = resolve_counter(opts.mode, opts.counter, opts.time, opts.period)?;
+    hotp(&key, counter, opts.algorithm, opts.digits)
+}
+
+/// Verify a code, allowing `window` steps of drift (TOTP: both directions, HOTP: look-ahead)
+pub fn process_otp_verify(opts: &OtpVerifyOpts) -> Result<bool> {
+    let key = decode_secret(&opts.secret)?;
+    let code = opts.code.trim();
+    let counter = resolve_counter(opts.mode, opts.counter, opts.time, opts.period)?;
+
+    let start = match opts.mode {
+        OtpMode::Totp => counter.saturating_sub(opts.window),
+        OtpMode::Hotp => counter,
+    };
+    let end = counter.saturating_add(opts.window);
+
+    for candidate in start..=end {
+        if hotp(&key, candidate, opts.algorithm, opts.digits)? == code {
+            tracing::info!(
+                counter = candidate,
+                drift = candidate as i128 - counter as i128,
+                "otp_verified"
+            );
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Generate a random secret and the matching `otpauth://` provisioning URI
+pub fn process_otp_secret_new(opts: &OtpSecretNewOpts) -> Result<(String, String)> {
+    let mut secret = vec![0u8; opts.bytes as usize];
+    rand::rng().fill_bytes(&mut secret);
+    let secret = BASE32_NOPAD.encode(&secret);
+
+    let label = match &opts.issuer {
+        Some(issuer) => format!(
+            "{}:{}",
+            percent_encode(issuer),
+            percent_encode(&opts.account)
+        ),
+        None => percent_encode(&opts.account),
+    };
+    let mut uri = format!(
+        "otpauth://{}/{}?secret={}&algorithm={}&digits={}",
+        opts.mode,
+        label,
+        secret,
+        opts.algorithm.to_string().to_ascii_uppercase(),
+        opts.digits
+    );
+    match opts.mode {
+        OtpMode::Totp => uri.push_str(&format!("&period={}", opts.period)),
+        OtpMode::Hotp => uri.push_str("&counter=0"),
+    }
+    if let Some(issuer) = &opts.issuer {
+        uri.push_str(&format!("&issuer={}", percent_encode(issuer)));
+    }
+    Ok((secret, uri))
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 Appendix B uses ASCII seeds of 20, 32 and 64 bytes
+    const SEED_SHA1: &[u8] = b"12345678901234567890";
+    const SEED_SHA256: &[u8] = b"12345678901234567890123456789012";
+    const SEED_SHA512: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    #[test]
+    fn test_hotp_rfc4226_vectors() -> Result<()> {
+        let expected = ["755224", "287082", "359152", "969429", "338314"];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(
+                hotp(SEED_SHA1, counter as u64, OtpAlgorithm::Sha1, 6)?,
+                *code
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_totp_rfc6238_vectors() -> Result<()> {
+        let cases = [
+            (SEED_SHA1, OtpAlgorithm::Sha1, 59, "94287082"),
+            (SEED_SHA256, OtpAlgorithm::Sha256, 59, "46119246"),
+            (SEED_SHA512, OtpAlgorithm::Sha512, 59, "90693936"),
+            (SEED_SHA1, OtpAlgorithm::Sha1, 1111111109, "07081804"),
+            (SEED_SHA512, OtpAlgorithm::Sha512, 20000000000, "47863826"),
+        ];
+        for (seed, algorithm, time, code) in cases {
+            let opts = OtpGenerateOpts {
+                secret: BASE32_NOPAD.encode(seed).into(),
+                mode: OtpMode::Totp,
+                algorithm,
+                digits: 8,
+                period: 30,
+                time: Some(time),
+                counter: None,
+            };
+            assert_eq!(process_otp_generate(&opts)?, code);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_totp_verify_window() -> Result<()> {
+        let mut opts = OtpVerifyOpts {
+            secret: BASE32_NOPAD.encode(SEED_SHA1).to_lowercase().into(),
+            code: "94287082".to_string(),
+            mode: OtpMode::Totp,
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 8,
+            period: 30,
+            time: Some(59 + 30),
+            counter: None,
+            window: 1,
+        };
+        assert!(process_otp_verify(&opts)?);
+        opts.window = 0;
+        assert!(!process_otp_verify(&opts)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hotp_requires_counter() {
+        let opts = OtpGenerateOpts {
+            secret: BASE32_NOPAD.encode(SEED_SHA1).into(),
+            mode: OtpMode::Hotp,
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 6,
+            period: 30,
+            time: None,
+            counter: None,
+        };
+        assert!(process_otp_generate(&opts).is_err());
+    }
+
+    #[test]
+    fn test_secret_new_uri() -> Result<()> {
+        let opts = OtpSecretNewOpts {
+            bytes: 20,
+            issuer: Some("ACME Co".to_string()),
+            account: "alice@example.com".to_string(),
+            mode: OtpMode::Totp,
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 6,
+            period: 30,
+        };
+        let (secret, uri) = process_otp_secret_new(&opts)?;
+        assert_eq!(decode_secret(&secret.clone().into())?.len(), 20);
+        assert!(uri.starts_with("otpauth://totp/ACME%20Co:alice%40example.com?secret="));
+        assert!(uri.ends_with("&period=30&issuer=ACME%20Co"));
+        Ok(())
+    }
+}
@@ -1,15 +1,38 @@
-use crate::cli::{GenPassOpts, TextSignFormat};
-use crate::process_genpass;
+use super::container::{
+    CipherHeader, KdfParams, KdfSpec, DEFAULT_SEGMENT_SIZE, FLAG_AAD, SALT_LEN, TAG_LEN,
+};
+use super::ec_key::{EcCurve, EcKey, EcSigningKey, EcVerifyingKey};
+use super::key_format::Ed25519Key;
+use super::key_protect::argon2id_key;
+use super::minisign::{MinisignPublicKey, MinisignSecretKey};
+use crate::cli::{KeyEncoding, KeyFormat, TextCipher, TextSignFormat};
 
 use rand::RngCore;
 
-use anyhow::{ensure, Result};
-use chacha20poly1305::aead::{Aead, KeyInit};
-use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
-use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
-use std::{collections::HashMap, io::Read};
+use aes_gcm::Aes256Gcm;
+use anyhow::{bail, ensure, Context, Result};
+use base64::{
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use chacha20poly1305::aead::consts::U5;
+use chacha20poly1305::aead::generic_array::{ArrayLength, GenericArray};
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+use chacha20poly1305::aead::{self, Aead, AeadInPlace, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce, XChaCha20Poly1305};
+use ed25519_dalek::{Digest, Sha512, Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    marker::PhantomData,
+    ops::Sub,
+    time::{SystemTime, UNIX_EPOCH},
+};
 #[cfg(test)]
 use std::{fs, path::Path};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 pub trait KeyGenerator {
     fn generate() -> Result<HashMap<&'static str, Vec<u8>>>;
@@ -18,34 +41,94 @@ pub trait KeyGenerator {
 trait TextSigner {
     /// Sign the data from the reader using the provided key and format.
     fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>>;
+    /// Fingerprint of the key, identical on the signing and verifying side.
+    fn key_id(&self) -> String;
 }
 
 trait TextVerifier {
     fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool>;
+    fn key_id(&self) -> String;
+}
+
+/// 自描述的分离签名文件（`<input>.sig`），以 JSON 保存
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetachedSignature {
+    pub algorithm: String,
+    /// HMAC 签名不记录：密钥可能是低熵的 webhook secret，指纹会成为离线猜测口令的依据
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    /// 签名时间（Unix 秒）
+    pub created: u64,
+    /// URL 安全、无填充的 Base64 签名
+    pub signature: String,
+}
+
+/// 密钥指纹：BLAKE3 派生后取前 8 字节的十六进制；Ed25519、ECDSA 基于公钥计算，Blake3 与 HMAC 不会泄露密钥本身
+fn fingerprint(key: &[u8]) -> String {
+    hex::encode(&blake3::derive_key("rst text key-id v1", key)[..8])
 }
 
 impl KeyGenerator for Blake3 {
+    /// 32 字节 CSPRNG 随机密钥（原始字节），写入磁盘前由 `encode_key` 编码
     fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
-        let opts = GenPassOpts::get_default_opts();
-        let key = process_genpass(&opts)?;
-        let key = key.as_bytes().to_vec();
-        Ok(HashMap::from([("blake3.txt", key)]))
+        let mut key = Zeroizing::new([0u8; 32]);
+        rand::rng().fill_bytes(key.as_mut_slice());
+        Ok(HashMap::from([("blake3.txt", key.to_vec())]))
     }
 }
 
-impl KeyGenerator for Ed25519Signer {
-    fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
+impl Blake3 {
+    /// 按 `encoding` 编码原始密钥，文本编码附带换行
+    fn encode_key(key: &[u8], encoding: KeyEncoding) -> Vec<u8> {
+        match encoding {
+            KeyEncoding::Hex => format!("{}\n", hex::encode(key)).into_bytes(),
+            KeyEncoding::Base64 => format!("{}\n", STANDARD.encode(key)).into_bytes(),
+            KeyEncoding::Raw => key.to_vec(),
+        }
+    }
+}
+
+impl Ed25519Signer {
+    fn generate(key_format: KeyFormat) -> Result<HashMap<&'static str, Vec<u8>>> {
         // rand` 0.9 uses `rand_core` 0.9
         // ed25519-dalek 3.0.0-pre.0 is compatible with rand_core 0.9
         // Generate 32 random bytes for the private key
-        let mut private_key_bytes = [0u8; 32];
-        rand::rng().fill_bytes(&mut private_key_bytes);
+        let mut private_key_bytes = Zeroizing::new([0u8; 32]);
+        rand::rng().fill_bytes(private_key_bytes.as_mut_slice());
 
-        let sk = SigningKey::from_bytes(&private_key_bytes);
-        let pk = sk.verifying_key().to_bytes().to_vec();
-        let sk = sk.to_bytes().to_vec();
+        let key = Ed25519Key::Secret(SigningKey::from_bytes(&private_key_bytes));
+        let (sk_name, pk_name) = ed25519_key_files(key_format);
+        let sk = key.encode(key_format, "")?;
+        let pk = key.to_public().encode(key_format, "")?;
+
+        Ok(HashMap::from([(sk_name, sk), (pk_name, pk)]))
+    }
+}
 
-        Ok(HashMap::from([("ed25519.sk", sk), ("ed25519.pk", pk)]))
+/// 不同密钥格式对应的（私钥, 公钥）文件名
+pub fn ed25519_key_files(key_format: KeyFormat) -> (&'static str, &'static str) {
+    match key_format {
+        KeyFormat::Raw => ("ed25519.sk", "ed25519.pk"),
+        KeyFormat::Pem => ("ed25519.pem", "ed25519.pub.pem"),
+        KeyFormat::OpenSsh => ("id_ed25519", "id_ed25519.pub"),
+        KeyFormat::Jwk => ("ed25519.jwk", "ed25519.pub.jwk"),
+    }
+}
+
+/// 非对称签名格式生成的（私钥, 公钥）文件名；对称密钥与 minisign 返回 `None`
+pub fn key_pair_files(
+    format: TextSignFormat,
+    key_format: KeyFormat,
+) -> Option<(&'static str, &'static str)> {
+    match (format, key_format) {
+        (TextSignFormat::Ed25519 | TextSignFormat::Ed25519ph, _) => {
+            Some(ed25519_key_files(key_format))
+        }
+        (TextSignFormat::EcdsaP256, KeyFormat::Raw) => Some(("p256.sk", "p256.pk")),
+        (TextSignFormat::EcdsaP256, _) => Some(("p256.pem", "p256.pub.pem")),
+        (TextSignFormat::EcdsaSecp256k1, KeyFormat::Raw) => Some(("secp256k1.sk", "secp256k1.pk")),
+        (TextSignFormat::EcdsaSecp256k1, _) => Some(("secp256k1.pem", "secp256k1.pub.pem")),
+        _ => None,
     }
 }
 
@@ -68,15 +151,15 @@ impl Ed25519Signer {
     //     Ok(signer)
     // }
 
+    /// 接受原始 32 字节私钥，以及 PKCS#8 PEM、OpenSSH、JWK 格式的私钥
     pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
-        let key = key.as_ref();
-        ensure!(key.len() >= 32, "Key must be at least 32 bytes");
-        let key = (&key[..32]).try_into()?;
-        Ok(Self::new(key))
+        match Ed25519Key::parse(key.as_ref(), false)? {
+            Ed25519Key::Secret(key) => Ok(Self::new(key)),
+            Ed25519Key::Public(_) => bail!("A private key is required for signing"),
+        }
     }
 
-    pub fn new(key: &[u8; 32]) -> Self {
-        let key = SigningKey::from_bytes(key);
+    pub fn new(key: SigningKey) -> Self {
         Self { key }
     }
 
@@ -91,6 +174,11 @@ struct Ed25519Verifier {
     key: VerifyingKey,
 }
 
+/// Ed25519ph（RFC 8032 预哈希变体）：先对输入做流式 SHA-512，再对摘要签名，内存占用恒定
+struct Ed25519phSigner(Ed25519Signer);
+
+struct Ed25519phVerifier(Ed25519Verifier);
+
 impl Ed25519Verifier {
     // pub fn new(key: VerifyingKey) -> Self {
     //     Self { key }
@@ -106,11 +194,9 @@ impl Ed25519Verifier {
     //     Ok(verifier)
     // }
 
+    /// 接受原始 32 字节公钥、SPKI PEM、OpenSSH、JWK；传入私钥时使用其对应的公钥
     pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
-        let key = key.as_ref();
-        ensure!(key.len() >= 32, "Key must be at least 32 bytes");
-        let key = (&key[..32]).try_into()?;
-        let key = VerifyingKey::from_bytes(key)?;
+        let key = Ed25519Key::parse(key.as_ref(), true)?.verifying_key();
         Ok(Self { key })
     }
 
@@ -123,12 +209,15 @@ impl Ed25519Verifier {
 
 // Blake3
 struct Blake3 {
-    key: [u8; 32],
+    /// 释放时清零
+    key: Zeroizing<[u8; 32]>,
 }
 
 impl Blake3 {
     pub fn new(key: [u8; 32]) -> Self {
-        Blake3 { key }
+        Blake3 {
+            key: Zeroizing::new(key),
+        }
     }
     // pub fn try_new(key: &[u8]) -> Result<Self> {
     //     let key = &key[..32];
@@ -137,8 +226,12 @@ impl Blake3 {
     //     Ok(signer)
     // }
     // impl AsRef<[u8]>，因此既可以直接传切片，也可以传 Vec<u8>、数组引用等任何能借用为 &[u8] 的类型，调用更灵活
+    /// 接受原始 32 字节、64 位十六进制或 Base64 文本；其余输入取前 32 字节，兼容旧版密钥文件
     pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
         let key = key.as_ref();
+        if let Some(decoded) = decode_text_key(key) {
+            return Ok(Self::new(*decoded));
+        }
         ensure!(key.len() >= 32, "Key must be at least 32 bytes");
         let key = (&key[..32]).try_into()?;
         Ok(Self::new(key))
@@ -151,22 +244,51 @@ impl Blake3 {
     }
 }
 
+/// 解码十六进制或 Base64（有无填充均可）表示的 32 字节密钥；原始 32 字节输入不做解码
+fn decode_text_key(data: &[u8]) -> Option<Zeroizing<[u8; 32]>> {
+    if data.len() == 32 {
+        return None;
+    }
+    let text = std::str::from_utf8(data).ok()?.trim();
+    let mut key = Zeroizing::new([0u8; 32]);
+    match text.len() {
+        64 => hex::decode_to_slice(text, key.as_mut_slice()).ok()?,
+        43 | 44 => {
+            let decoded = Zeroizing::new(STANDARD_NO_PAD.decode(text.trim_end_matches('=')).ok()?);
+            key.copy_from_slice(decoded.get(..32).filter(|_| decoded.len() == 32)?);
+        }
+        _ => return None,
+    }
+    Some(key)
+}
+
+impl Blake3 {
+    /// 增量计算 keyed hash，避免把整个输入读入内存
+    fn keyed_hash(&self, reader: &mut dyn Read) -> Result<blake3::Hash> {
+        let mut hasher = blake3::Hasher::new_keyed(&self.key);
+        io::copy(reader, &mut hasher)?;
+        Ok(hasher.finalize())
+    }
+}
+
 impl TextSigner for Blake3 {
     fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-        Ok(blake3::keyed_hash(&self.key, &buf).as_bytes().to_vec())
+        Ok(self.keyed_hash(reader)?.as_bytes().to_vec())
+    }
+
+    fn key_id(&self) -> String {
+        fingerprint(self.key.as_slice())
     }
 }
 
 impl TextVerifier for Blake3 {
     fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-        // blake3::hash(&buf).as_bytes() == sig temporary value which is freed while still in use
-        let hash = blake3::keyed_hash(&self.key, &buf);
-        let hash = hash.as_bytes();
-        Ok(hash == sig)
+        // blake3::Hash 的 PartialEq<[u8]> 为常量时间比较
+        Ok(self.keyed_hash(reader)? == *sig)
+    }
+
+    fn key_id(&self) -> String {
+        fingerprint(self.key.as_slice())
     }
 }
 
@@ -177,6 +299,10 @@ impl TextSigner for Ed25519Signer {
         let signature = self.key.sign(&buf);
         Ok(signature.to_bytes().to_vec())
     }
+
+    fn key_id(&self) -> String {
+        fingerprint(self.key.verifying_key().as_bytes())
+    }
 }
 
 impl TextVerifier for Ed25519Verifier {
@@ -187,69 +313,639 @@ impl TextVerifier for Ed25519Verifier {
         let ret = self.key.verify(&buf, &sig).is_ok();
         Ok(ret)
     }
+
+    fn key_id(&self) -> String {
+        fingerprint(self.key.as_bytes())
+    }
 }
 
-fn derive_chacha_key(key: &[u8]) -> [u8; 32] {
-    if key.len() == 32 {
-        let mut out = [0u8; 32];
-        out.copy_from_slice(key);
-        out
-    } else {
-        let hash = blake3::hash(key);
-        *hash.as_bytes()
+fn prehash(reader: &mut dyn Read) -> Result<Sha512> {
+    let mut hasher = Sha512::new();
+    io::copy(reader, &mut DigestWriter(&mut hasher))?;
+    Ok(hasher)
+}
+
+/// ed25519-dalek 使用的 sha2 预发布版未实现 `io::Write`，包一层以便直接 `io::copy`
+struct DigestWriter<'a, D>(&'a mut D);
+
+impl<D: Digest> Write for DigestWriter<'_, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
 
-// cargo run -- text encrypt --key "hello" --text "hello world"
-// cargo run -- text decrypt --key "hello" --cipher  ETzc+ijUW2Yb0Nvf0HzZthdDc+FoyY/+hWpvPXK/dsf90cIGwagQ
+impl TextSigner for Ed25519phSigner {
+    fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
+        let signature = self.0.key.sign_prehashed(prehash(reader)?, None)?;
+        Ok(signature.to_bytes().to_vec())
+    }
+
+    fn key_id(&self) -> String {
+        self.0.key_id()
+    }
+}
+
+impl TextVerifier for Ed25519phVerifier {
+    fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool> {
+        let sig = Signature::from_bytes(sig.try_into()?);
+        let ret = self
+            .0
+            .key
+            .verify_prehashed(prehash(reader)?, None, &sig)
+            .is_ok();
+        Ok(ret)
+    }
+
+    fn key_id(&self) -> String {
+        self.0.key_id()
+    }
+}
+
+/// HMAC（RFC 2104），与 webhook 服务商常用的 `X-Signature` 方式一致；密钥为文件内容
+/// （去掉末尾一个换行），因此服务商给出的 secret 字符串可以直接保存为密钥文件
+struct HmacKey<M> {
+    /// 释放时清零
+    key: Zeroizing<Vec<u8>>,
+    mac: PhantomData<M>,
+}
+
+type HmacSha256 = HmacKey<Hmac<sha2::Sha256>>;
+type HmacSha512 = HmacKey<Hmac<sha2::Sha512>>;
+
+impl<M: Mac + KeyInit + Write> HmacKey<M> {
+    pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
+        let key = key.as_ref();
+        let key = key.strip_suffix(b"\n").unwrap_or(key);
+        let key = key.strip_suffix(b"\r").unwrap_or(key);
+        ensure!(!key.is_empty(), "HMAC key cannot be empty");
+        Ok(Self {
+            key: Zeroizing::new(key.to_vec()),
+            mac: PhantomData,
+        })
+    }
 
-fn build_cipher(key: &[u8]) -> Result<ChaCha20Poly1305> {
-    let key = derive_chacha_key(key);
-    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    /// 增量计算 MAC，避免把整个输入读入内存
+    fn digest(&self, reader: &mut dyn Read) -> Result<M> {
+        let mut mac = <M as Mac>::new_from_slice(&self.key)
+            .map_err(|e| anyhow::anyhow!("Invalid HMAC key: {e}"))?;
+        io::copy(reader, &mut mac)?;
+        Ok(mac)
+    }
+}
+
+impl<M> KeyGenerator for HmacKey<M> {
+    /// 32 字节 CSPRNG 随机数的十六进制文本，文本本身即为密钥，便于配置到 webhook 服务商
+    fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        rand::rng().fill_bytes(key.as_mut_slice());
+        Ok(HashMap::from([(
+            "hmac.txt",
+            format!("{}\n", hex::encode(key.as_slice())).into_bytes(),
+        )]))
+    }
 }
 
-pub fn process_text_encrypt(reader: &mut dyn Read, key: &[u8]) -> Result<Vec<u8>> {
-    let cipher = build_cipher(key)?;
-    let mut plaintext = Vec::new();
+impl<M: Mac + KeyInit + Write> TextSigner for HmacKey<M> {
+    fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
+        Ok(self.digest(reader)?.finalize().into_bytes().to_vec())
+    }
+
+    fn key_id(&self) -> String {
+        fingerprint(&self.key)
+    }
+}
+
+impl<M: Mac + KeyInit + Write> TextVerifier for HmacKey<M> {
+    fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool> {
+        // verify_slice 为常量时间比较
+        Ok(self.digest(reader)?.verify_slice(sig).is_ok())
+    }
+
+    fn key_id(&self) -> String {
+        fingerprint(&self.key)
+    }
+}
+
+/// ECDSA（ES256 / ES256K）：对输入的 SHA-256 摘要签名，密钥格式见 ec_key 模块
+struct EcdsaSigner(EcSigningKey);
+
+struct EcdsaVerifier(EcVerifyingKey);
+
+/// 按曲线区分的密钥生成器，生成原始格式的密钥对
+struct EcdsaP256;
+
+struct EcdsaSecp256k1;
+
+impl EcdsaSigner {
+    /// 接受原始 32 字节私钥（或其十六进制）、PKCS#8 PEM 与 OpenSSL 的 `EC PRIVATE KEY`
+    pub fn try_new(curve: EcCurve, key: impl AsRef<[u8]>) -> Result<Self> {
+        match EcKey::parse(curve, key.as_ref())? {
+            EcKey::Secret(key) => Ok(Self(key)),
+            EcKey::Public(_) => bail!("A private key is required for signing"),
+        }
+    }
+
+    fn generate(curve: EcCurve) -> Result<HashMap<&'static str, Vec<u8>>> {
+        let format = match curve {
+            EcCurve::P256 => TextSignFormat::EcdsaP256,
+            EcCurve::Secp256k1 => TextSignFormat::EcdsaSecp256k1,
+        };
+        let (sk_name, pk_name) = key_pair_files(format, KeyFormat::Raw)
+            .context("ECDSA formats always have a key pair")?;
+        let key = EcSigningKey::generate(curve);
+        let sk = key.encode(KeyFormat::Raw)?;
+        let pk = key.verifying_key().encode(KeyFormat::Raw)?;
+        Ok(HashMap::from([(sk_name, sk), (pk_name, pk)]))
+    }
+
+    /// 把生成的原始密钥对转换为 `key_format`（raw 或 PEM）
+    fn encode_keys(
+        format: TextSignFormat,
+        keys: HashMap<&'static str, Vec<u8>>,
+        key_format: KeyFormat,
+    ) -> Result<HashMap<&'static str, Vec<u8>>> {
+        let curve = format.curve().context("Not an ECDSA format")?;
+        let (raw_sk, _) = key_pair_files(format, KeyFormat::Raw).context("Not an ECDSA format")?;
+        let sk = keys
+            .get(raw_sk)
+            .context("ECDSA generator did not return key pair")?;
+        let key = Self::try_new(curve, sk)?.0;
+        let (sk_name, pk_name) =
+            key_pair_files(format, key_format).context("Not an ECDSA format")?;
+        Ok(HashMap::from([
+            (sk_name, key.encode(key_format)?),
+            (pk_name, key.verifying_key().encode(key_format)?),
+        ]))
+    }
+}
+
+impl EcdsaVerifier {
+    /// 接受原始 SEC1 公钥（或其十六进制）与 SPKI PEM；传入私钥时使用其对应的公钥
+    pub fn try_new(curve: EcCurve, key: impl AsRef<[u8]>) -> Result<Self> {
+        Ok(Self(EcKey::parse(curve, key.as_ref())?.verifying_key()))
+    }
+}
+
+impl KeyGenerator for EcdsaP256 {
+    fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
+        EcdsaSigner::generate(EcCurve::P256)
+    }
+}
+
+impl KeyGenerator for EcdsaSecp256k1 {
+    fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
+        EcdsaSigner::generate(EcCurve::Secp256k1)
+    }
+}
+
+impl TextSigner for EcdsaSigner {
+    fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
+        self.0.sign(reader)
+    }
+
+    fn key_id(&self) -> String {
+        fingerprint(&self.0.verifying_key().to_sec1())
+    }
+}
+
+impl TextVerifier for EcdsaVerifier {
+    fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool> {
+        self.0.verify(reader, sig)
+    }
+
+    fn key_id(&self) -> String {
+        fingerprint(&self.0.to_sec1())
+    }
+}
+
+/// 文本加密使用的密钥：口令经 Argon2id 派生，原始 32 字节密钥直接使用；释放时清零
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub enum EncryptionKey {
+    Passphrase(Vec<u8>),
+    Raw([u8; 32]),
+}
+
+impl EncryptionKey {
+    /// 解析原始密钥文件：32 字节二进制或 64 位十六进制文本
+    pub fn raw_from_bytes(data: &[u8]) -> Result<Self> {
+        if let Ok(key) = <[u8; 32]>::try_from(data) {
+            return Ok(Self::Raw(key));
+        }
+        let text = std::str::from_utf8(data).map(str::trim).unwrap_or_default();
+        let mut key = Zeroizing::new([0u8; 32]);
+        ensure!(
+            text.len() == 64 && hex::decode_to_slice(text, key.as_mut_slice()).is_ok(),
+            "Raw key must be 32 bytes or 64 hex characters"
+        );
+        Ok(Self::Raw(*key))
+    }
+
+    /// 按头部记录的 KDF 得到 32 字节加密密钥
+    fn derive(&self, kdf: &KdfSpec) -> Result<Zeroizing<[u8; 32]>> {
+        match (kdf, self) {
+            (KdfSpec::Raw, EncryptionKey::Raw(key)) => Ok(Zeroizing::new(*key)),
+            (KdfSpec::Argon2id { params, salt }, EncryptionKey::Passphrase(passphrase)) => {
+                argon2id_key(
+                    passphrase,
+                    salt,
+                    params.memory,
+                    params.iterations,
+                    params.parallelism,
+                )
+            }
+            (KdfSpec::Raw, EncryptionKey::Passphrase(_)) => {
+                bail!("Ciphertext was encrypted with a raw key; use --raw-key")
+            }
+            (KdfSpec::Argon2id { .. }, EncryptionKey::Raw(_)) => {
+                bail!("Ciphertext was encrypted with a passphrase; use --key")
+            }
+        }
+    }
+}
+
+// cargo run -- text encrypt --key "hello" --text "hello world"
+// cargo run -- text decrypt --key "hello" --ciphertext <BASE64>
+// cargo run -- text encrypt --key "hello" -i backup.tar -o backup.tar.rste
+// cargo run -- text decrypt --key "hello" -i backup.tar.rste -o backup.tar
+// 输出格式见 container 模块
+
+pub fn process_text_encrypt(
+    reader: &mut dyn Read,
+    key: &EncryptionKey,
+    cipher: TextCipher,
+    params: &KdfParams,
+    aad: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    let mut plaintext = Zeroizing::new(Vec::new());
     reader.read_to_end(&mut plaintext)?;
 
-    let mut nonce = [0u8; 12];
+    let (kdf, cipher_key) = new_message_key(key, params)?;
+    let mut nonce = vec![0u8; cipher.nonce_len()];
     rand::rng().fill_bytes(&mut nonce);
+    let header = CipherHeader::new(cipher, kdf, aad_flags(aad), nonce);
 
-    let ciphertext = cipher
-        .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+    let mut output = header.encode();
+    let aad = bind_aad(&header, &output, aad)?;
+    let payload = Payload {
+        msg: &plaintext,
+        aad: &aad,
+    };
+    let ciphertext = aead_encrypt(cipher, &cipher_key, &header.nonce, payload)
         .map_err(|e| anyhow::anyhow!("Encryption failed: {e}"))?;
 
-    let mut output = Vec::with_capacity(nonce.len() + ciphertext.len());
-    output.extend_from_slice(&nonce);
     output.extend_from_slice(&ciphertext);
     Ok(output)
 }
 
-pub fn process_text_decrypt(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+/// 分段（STREAM）加密：边读边写二进制容器，内存占用与输入大小无关
+pub fn process_text_encrypt_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    key: &EncryptionKey,
+    cipher: TextCipher,
+    params: &KdfParams,
+    aad: Option<&[u8]>,
+) -> Result<()> {
+    let (kdf, cipher_key) = new_message_key(key, params)?;
+    let mut header = CipherHeader::new(cipher, kdf, aad_flags(aad), Vec::new());
+    header.segment_size = Some(DEFAULT_SEGMENT_SIZE);
+    header.nonce = vec![0u8; header.nonce_len()];
+    rand::rng().fill_bytes(&mut header.nonce);
+    let header_bytes = header.encode();
+    writer.write_all(&header_bytes)?;
+    let aad = bind_aad(&header, &header_bytes, aad)?;
+
+    let segment_size = DEFAULT_SEGMENT_SIZE as usize;
+    let (nonce, key) = (&header.nonce[..], &cipher_key);
+    match cipher {
+        TextCipher::ChaCha20Poly1305 => {
+            seal_segments::<ChaCha20Poly1305>(key, nonce, &aad, segment_size, reader, writer)
+        }
+        TextCipher::XChaCha20Poly1305 => {
+            seal_segments::<XChaCha20Poly1305>(key, nonce, &aad, segment_size, reader, writer)
+        }
+        TextCipher::Aes256Gcm => {
+            seal_segments::<Aes256Gcm>(key, nonce, &aad, segment_size, reader, writer)
+        }
+    }
+}
+
+/// 算法由头部决定；`cipher` 非空时要求与头部记录一致，`aad` 必须与加密时一致
+pub fn process_text_decrypt(
+    data: &[u8],
+    key: &EncryptionKey,
+    cipher: Option<TextCipher>,
+    aad: Option<&[u8]>,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let Some((header, header_len)) = CipherHeader::parse(data)? else {
+        check_cipher(TextCipher::ChaCha20Poly1305, cipher)?;
+        ensure!(
+            aad.is_none(),
+            "Legacy ciphertext has no associated data; remove --aad"
+        );
+        return decrypt_legacy(data, key);
+    };
+    check_cipher(header.cipher, cipher)?;
+    let (header_bytes, ciphertext) = data.split_at(header_len);
+    let aad = bind_aad(&header, header_bytes, aad)?;
+    if header.segment_size.is_some() {
+        let mut plaintext = Zeroizing::new(Vec::new());
+        decrypt_segments(&header, &aad, &mut &ciphertext[..], &mut *plaintext, key)?;
+        return Ok(plaintext);
+    }
+    decrypt_message(&header, &aad, ciphertext, key)
+}
+
+/// 从流中解密二进制容器；分段加密的内容逐段认证后写出，截断或篡改会在出错的分段处报错
+pub fn process_text_decrypt_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    key: &EncryptionKey,
+    cipher: Option<TextCipher>,
+    aad: Option<&[u8]>,
+) -> Result<()> {
+    let (header, header_bytes) = CipherHeader::read_from(reader)?;
+    check_cipher(header.cipher, cipher)?;
+    let aad = bind_aad(&header, &header_bytes, aad)?;
+    if header.segment_size.is_some() {
+        return decrypt_segments(&header, &aad, reader, writer, key);
+    }
+    let mut ciphertext = Vec::new();
+    reader.read_to_end(&mut ciphertext)?;
+    writer.write_all(&decrypt_message(&header, &aad, &ciphertext, key)?)?;
+    Ok(())
+}
+
+fn check_cipher(actual: TextCipher, expected: Option<TextCipher>) -> Result<()> {
+    match expected {
+        Some(expected) if expected != actual => {
+            bail!("Ciphertext uses {}, not {}", actual, expected)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 为新消息生成头部的 KDF 字段（口令使用随机盐）并得到加密密钥
+fn new_message_key(
+    key: &EncryptionKey,
+    params: &KdfParams,
+) -> Result<(KdfSpec, Zeroizing<[u8; 32]>)> {
+    let kdf = match key {
+        EncryptionKey::Raw(_) => KdfSpec::Raw,
+        EncryptionKey::Passphrase(passphrase) => {
+            ensure!(!passphrase.is_empty(), "Passphrase cannot be empty");
+            // 超出上限的参数生成的密文将无法解密
+            params.check_limits()?;
+            let mut salt = [0u8; SALT_LEN];
+            rand::rng().fill_bytes(&mut salt);
+            KdfSpec::Argon2id {
+                params: *params,
+                salt,
+            }
+        }
+    };
+    let cipher_key = key.derive(&kdf)?;
+    Ok((kdf, cipher_key))
+}
+
+fn aad_flags(aad: Option<&[u8]>) -> u8 {
+    if aad.is_some() {
+        FLAG_AAD
+    } else {
+        0
+    }
+}
+
+/// AEAD 关联数据为 `头部 || 外部关联数据`；头部自带长度信息，拼接不会产生歧义
+fn bind_aad(header: &CipherHeader, header_bytes: &[u8], aad: Option<&[u8]>) -> Result<Vec<u8>> {
+    match (header.flags & FLAG_AAD != 0, aad) {
+        (true, None) => {
+            bail!("Ciphertext is bound to associated data; supply it with --aad or --aad-file")
+        }
+        (false, Some(_)) => bail!("Ciphertext has no associated data; remove --aad"),
+        (_, aad) => Ok([header_bytes, aad.unwrap_or_default()].concat()),
+    }
+}
+
+fn aead_encrypt(
+    cipher: TextCipher,
+    key: &[u8; 32],
+    nonce: &[u8],
+    payload: Payload,
+) -> aead::Result<Vec<u8>> {
+    let key = key.into();
+    match cipher {
+        TextCipher::ChaCha20Poly1305 => {
+            ChaCha20Poly1305::new(key).encrypt(GenericArray::from_slice(nonce), payload)
+        }
+        TextCipher::XChaCha20Poly1305 => {
+            XChaCha20Poly1305::new(key).encrypt(GenericArray::from_slice(nonce), payload)
+        }
+        TextCipher::Aes256Gcm => {
+            Aes256Gcm::new(key).encrypt(GenericArray::from_slice(nonce), payload)
+        }
+    }
+}
+
+fn aead_decrypt(
+    cipher: TextCipher,
+    key: &[u8; 32],
+    nonce: &[u8],
+    payload: Payload,
+) -> aead::Result<Vec<u8>> {
+    let key = key.into();
+    match cipher {
+        TextCipher::ChaCha20Poly1305 => {
+            ChaCha20Poly1305::new(key).decrypt(GenericArray::from_slice(nonce), payload)
+        }
+        TextCipher::XChaCha20Poly1305 => {
+            XChaCha20Poly1305::new(key).decrypt(GenericArray::from_slice(nonce), payload)
+        }
+        TextCipher::Aes256Gcm => {
+            Aes256Gcm::new(key).decrypt(GenericArray::from_slice(nonce), payload)
+        }
+    }
+}
+
+fn decrypt_message(
+    header: &CipherHeader,
+    aad: &[u8],
+    ciphertext: &[u8],
+    key: &EncryptionKey,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let cipher_key = key.derive(&header.kdf)?;
+    let payload = Payload {
+        msg: ciphertext,
+        aad,
+    };
+    aead_decrypt(header.cipher, &cipher_key, &header.nonce, payload)
+        .map(Zeroizing::new)
+        .map_err(|_| anyhow::anyhow!("Decryption failed: wrong key or corrupted ciphertext"))
+}
+
+fn decrypt_segments(
+    header: &CipherHeader,
+    aad: &[u8],
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    key: &EncryptionKey,
+) -> Result<()> {
+    let cipher_key = key.derive(&header.kdf)?;
+    let segment_size = header.segment_size.unwrap_or(DEFAULT_SEGMENT_SIZE) as usize + TAG_LEN;
+    let (nonce, key) = (&header.nonce[..], &cipher_key);
+    match header.cipher {
+        TextCipher::ChaCha20Poly1305 => {
+            open_segments::<ChaCha20Poly1305>(key, nonce, aad, segment_size, reader, writer)
+        }
+        TextCipher::XChaCha20Poly1305 => {
+            open_segments::<XChaCha20Poly1305>(key, nonce, aad, segment_size, reader, writer)
+        }
+        TextCipher::Aes256Gcm => {
+            open_segments::<Aes256Gcm>(key, nonce, aad, segment_size, reader, writer)
+        }
+    }
+}
+
+fn seal_segments<A>(
+    key: &[u8; 32],
+    nonce: &[u8],
+    aad: &[u8],
+    segment_size: usize,
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+) -> Result<()>
+where
+    A: AeadInPlace + KeyInit,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArrayLength<u8>,
+{
+    let cipher = A::new_from_slice(key).map_err(|e| anyhow::anyhow!("Invalid key: {e}"))?;
+    let mut encryptor = EncryptorBE32::from_aead(cipher, GenericArray::from_slice(nonce));
+    let mut current = read_segment(reader, segment_size)?;
+    loop {
+        // 预读下一段以判断当前段是否为末段
+        let next = read_segment(reader, segment_size)?;
+        let payload = Payload { msg: &current, aad };
+        if next.is_empty() {
+            let sealed = encryptor
+                .encrypt_last(payload)
+                .map_err(|e| anyhow::anyhow!("Encryption failed: {e}"))?;
+            writer.write_all(&sealed)?;
+            return Ok(());
+        }
+        let sealed = encryptor
+            .encrypt_next(payload)
+            .map_err(|e| anyhow::anyhow!("Encryption failed: {e}"))?;
+        writer.write_all(&sealed)?;
+        current = next;
+    }
+}
+
+fn open_segments<A>(
+    key: &[u8; 32],
+    nonce: &[u8],
+    aad: &[u8],
+    segment_size: usize,
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+) -> Result<()>
+where
+    A: AeadInPlace + KeyInit,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArrayLength<u8>,
+{
+    let cipher = A::new_from_slice(key).map_err(|e| anyhow::anyhow!("Invalid key: {e}"))?;
+    let mut decryptor = DecryptorBE32::from_aead(cipher, GenericArray::from_slice(nonce));
+    let failed = |index: u32| {
+        anyhow::anyhow!(
+            "Decryption failed at segment {}: wrong key, or truncated, reordered or corrupted stream",
+            index
+        )
+    };
+    let mut index = 0;
+    let mut current = read_segment(reader, segment_size)?;
+    loop {
+        let next = read_segment(reader, segment_size)?;
+        let payload = Payload { msg: &current, aad };
+        if next.is_empty() {
+            let plaintext =
+                Zeroizing::new(decryptor.decrypt_last(payload).map_err(|_| failed(index))?);
+            writer.write_all(&plaintext)?;
+            return Ok(());
+        }
+        let plaintext = Zeroizing::new(decryptor.decrypt_next(payload).map_err(|_| failed(index))?);
+        writer.write_all(&plaintext)?;
+        current = next;
+        index += 1;
+    }
+}
+
+/// 读取至多 `size` 字节，仅在输入结束时返回不足一段的数据；加密时分段为明文，释放时清零
+fn read_segment(reader: &mut dyn Read, size: usize) -> Result<Zeroizing<Vec<u8>>> {
+    let mut segment = Zeroizing::new(Vec::with_capacity(size));
+    reader.take(size as u64).read_to_end(&mut segment)?;
+    Ok(segment)
+}
+
+/// 旧版输出 `nonce(12) || 密文`：32 字节密钥直接使用，否则取无盐的 BLAKE3 哈希
+fn decrypt_legacy(data: &[u8], key: &EncryptionKey) -> Result<Zeroizing<Vec<u8>>> {
     ensure!(
         data.len() >= 12,
         "Ciphertext too short; expected nonce + payload"
     );
+    let key = Zeroizing::new(match key {
+        EncryptionKey::Raw(key) => *key,
+        EncryptionKey::Passphrase(key) if key.len() == 32 => key.as_slice().try_into()?,
+        EncryptionKey::Passphrase(key) => *blake3::hash(key).as_bytes(),
+    });
     let (nonce_bytes, cipher_bytes) = data.split_at(12);
-    let cipher = build_cipher(key)?;
-    let plaintext = cipher
+    ChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
         .decrypt(Nonce::from_slice(nonce_bytes), cipher_bytes)
-        .map_err(|e| anyhow::anyhow!("Decryption failed: {e}"))?;
-    Ok(plaintext)
+        .map(Zeroizing::new)
+        .map_err(|e| anyhow::anyhow!("Decryption failed: {e}"))
 }
 
-pub fn process_text_sign(
-    reader: &mut dyn Read,
-    key: &[u8],
-    format: TextSignFormat,
-) -> Result<Vec<u8>> {
+fn build_signer(key: &[u8], format: TextSignFormat) -> Result<Box<dyn TextSigner>> {
     let signer: Box<dyn TextSigner> = match format {
         TextSignFormat::Blake3 => Box::new(Blake3::try_new(key)?),
         TextSignFormat::Ed25519 => Box::new(Ed25519Signer::try_new(key)?),
+        TextSignFormat::Ed25519ph => Box::new(Ed25519phSigner(Ed25519Signer::try_new(key)?)),
+        TextSignFormat::HmacSha256 => Box::new(HmacSha256::try_new(key)?),
+        TextSignFormat::HmacSha512 => Box::new(HmacSha512::try_new(key)?),
+        TextSignFormat::EcdsaP256 => Box::new(EcdsaSigner::try_new(EcCurve::P256, key)?),
+        TextSignFormat::EcdsaSecp256k1 => Box::new(EcdsaSigner::try_new(EcCurve::Secp256k1, key)?),
+        TextSignFormat::Minisign => bail!("minisign keys only produce .minisig signature files"),
     };
+    Ok(signer)
+}
 
-    signer.sign(reader)
+fn build_verifier(key: &[u8], format: TextSignFormat) -> Result<Box<dyn TextVerifier>> {
+    let verifier: Box<dyn TextVerifier> = match format {
+        TextSignFormat::Blake3 => Box::new(Blake3::try_new(key)?),
+        TextSignFormat::Ed25519 => Box::new(Ed25519Verifier::try_new(key)?),
+        TextSignFormat::Ed25519ph => Box::new(Ed25519phVerifier(Ed25519Verifier::try_new(key)?)),
+        TextSignFormat::HmacSha256 => Box::new(HmacSha256::try_new(key)?),
+        TextSignFormat::HmacSha512 => Box::new(HmacSha512::try_new(key)?),
+        TextSignFormat::EcdsaP256 => Box::new(EcdsaVerifier::try_new(EcCurve::P256, key)?),
+        TextSignFormat::EcdsaSecp256k1 => {
+            Box::new(EcdsaVerifier::try_new(EcCurve::Secp256k1, key)?)
+        }
+        TextSignFormat::Minisign => bail!("minisign keys only verify .minisig signature files"),
+    };
+    Ok(verifier)
+}
+
+pub fn process_text_sign(
+    reader: &mut dyn Read,
+    key: &[u8],
+    format: TextSignFormat,
+) -> Result<Vec<u8>> {
+    build_signer(key, format)?.sign(reader)
 }
 
 pub fn process_text_verify(
@@ -258,24 +954,166 @@ pub fn process_text_verify(
     sig: &[u8],
     format: TextSignFormat,
 ) -> Result<bool> {
-    let verifier: Box<dyn TextVerifier> = match format {
-        TextSignFormat::Blake3 => Box::new(Blake3::try_new(key)?),
-        TextSignFormat::Ed25519 => Box::new(Ed25519Verifier::try_new(key)?),
+    build_verifier(key, format)?.verify(reader, sig)
+}
+
+pub fn process_text_sign_detached(
+    reader: &mut dyn Read,
+    key: &[u8],
+    format: TextSignFormat,
+) -> Result<DetachedSignature> {
+    let signer = build_signer(key, format)?;
+    let signature = signer.sign(reader)?;
+    let hmac = matches!(
+        format,
+        TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512
+    );
+    Ok(DetachedSignature {
+        algorithm: format.to_string(),
+        key_id: (!hmac).then(|| signer.key_id()),
+        created: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        signature: URL_SAFE_NO_PAD.encode(signature),
+    })
+}
+
+/// 校验分离签名；`format` 为 `Some` 时必须与签名文件记录的算法一致
+pub fn process_text_verify_detached(
+    reader: &mut dyn Read,
+    key: &[u8],
+    sig: &DetachedSignature,
+    format: Option<TextSignFormat>,
+) -> Result<bool> {
+    let algorithm: TextSignFormat = sig.algorithm.parse()?;
+    if let Some(format) = format {
+        ensure!(
+            format == algorithm,
+            "Signature algorithm mismatch: file was signed with {}, but {} was requested",
+            algorithm,
+            format
+        );
+    }
+    let verifier = build_verifier(key, algorithm)?;
+    if let Some(key_id) = &sig.key_id {
+        ensure!(
+            verifier.key_id() == *key_id,
+            "Key mismatch: signature was made by key {}, but the given key is {}",
+            key_id,
+            verifier.key_id()
+        );
+    }
+    let signature = URL_SAFE_NO_PAD.decode(sig.signature.trim())?;
+    verifier.verify(reader, &signature)
+}
+
+/// 使用 minisign 密钥文件生成 `.minisig` 内容；未指定 trusted comment 时与 minisign 默认格式一致
+pub fn process_text_sign_minisign(
+    reader: &mut dyn Read,
+    key: &[u8],
+    trusted_comment: Option<&str>,
+    file_name: &str,
+    prehash: bool,
+) -> Result<String> {
+    let key = MinisignSecretKey::decode(std::str::from_utf8(key)?)?;
+    let trusted_comment = match trusted_comment {
+        Some(comment) => comment.to_string(),
+        None => format!(
+            "timestamp:{}\tfile:{}{}",
+            SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            file_name,
+            if prehash { "\thashed" } else { "" }
+        ),
     };
-    verifier.verify(reader, sig)
+    key.sign(reader, &trusted_comment, prehash)
 }
 
-pub fn process_text_key_generate(format: TextSignFormat) -> Result<HashMap<&'static str, Vec<u8>>> {
+/// 校验 `.minisig`，成功时返回 trusted comment
+pub fn process_text_verify_minisign(
+    reader: &mut dyn Read,
+    key: &[u8],
+    minisig: &str,
+) -> Result<Option<String>> {
+    let key = MinisignPublicKey::decode(std::str::from_utf8(key)?)?;
+    Ok(key
+        .verify(reader, minisig)?
+        .map(|verified| verified.trusted_comment))
+}
+
+/// `key_format` 决定 Ed25519 / ECDSA 密钥文件格式，`encoding` 决定 Blake3 密钥的编码
+pub fn process_text_key_generate(
+    format: TextSignFormat,
+    key_format: KeyFormat,
+    encoding: KeyEncoding,
+) -> Result<HashMap<&'static str, Vec<u8>>> {
     match format {
-        TextSignFormat::Blake3 => Blake3::generate(),
-        TextSignFormat::Ed25519 => Ed25519Signer::generate(),
+        TextSignFormat::Blake3 => {
+            let mut keys = Blake3::generate()?;
+            for key in keys.values_mut() {
+                let raw = Zeroizing::new(std::mem::take(key));
+                *key = Blake3::encode_key(&raw, encoding);
+            }
+            Ok(keys)
+        }
+        TextSignFormat::Ed25519 | TextSignFormat::Ed25519ph => Ed25519Signer::generate(key_format),
+        TextSignFormat::HmacSha256 => HmacSha256::generate(),
+        TextSignFormat::HmacSha512 => HmacSha512::generate(),
+        TextSignFormat::EcdsaP256 => {
+            EcdsaSigner::encode_keys(format, EcdsaP256::generate()?, key_format)
+        }
+        TextSignFormat::EcdsaSecp256k1 => {
+            EcdsaSigner::encode_keys(format, EcdsaSecp256k1::generate()?, key_format)
+        }
+        TextSignFormat::Minisign => {
+            let sk = MinisignSecretKey::generate();
+            Ok(HashMap::from([
+                ("minisign.key", sk.encode().into_bytes()),
+                ("minisign.pub", sk.public_key().encode().into_bytes()),
+            ]))
+        }
+    }
+}
+
+/// 计算密钥指纹（与分离签名中的 `key_id` 一致）；`secret` 指明原始字节是否为私钥
+pub fn process_text_key_fingerprint(
+    key: &[u8],
+    format: TextSignFormat,
+    secret: bool,
+) -> Result<String> {
+    if secret {
+        Ok(build_signer(key, format)?.key_id())
+    } else {
+        Ok(build_verifier(key, format)?.key_id())
     }
 }
 
+/// 在 raw / PEM / OpenSSH / JWK 之间转换 Ed25519 密钥；`public` 为 true 时只导出公钥
+pub fn process_text_key_convert(
+    key: &[u8],
+    to: KeyFormat,
+    raw_is_public: bool,
+    public: bool,
+    comment: &str,
+) -> Result<Vec<u8>> {
+    let key = Ed25519Key::parse(key, raw_is_public)?;
+    let key = if public { key.to_public() } else { key };
+    key.encode(to, comment)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const ALL_CIPHERS: [TextCipher; 3] = [
+        TextCipher::ChaCha20Poly1305,
+        TextCipher::XChaCha20Poly1305,
+        TextCipher::Aes256Gcm,
+    ];
+
+    const FAST_KDF: KdfParams = KdfParams {
+        memory: 1024,
+        iterations: 1,
+        parallelism: 1,
+    };
+
     #[test]
     fn test_blake3_sign_verify() -> Result<()> {
         let blake3 = Blake3::load("fixtures/blake3.txt")?;
@@ -297,6 +1135,73 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_ed25519ph_sign_verify() -> Result<()> {
+        let sk = Ed25519phSigner(Ed25519Signer::load("fixtures/ed25519.sk")?);
+        let pk = Ed25519phVerifier(Ed25519Verifier::load("fixtures/ed25519.pk")?);
+
+        // 跨越多个读取块的输入
+        let data = vec![42u8; 200 * 1024];
+        let sig = sk.sign(&mut &data[..])?;
+        assert!(pk.verify(&mut &data[..], &sig)?);
+        assert!(!pk.verify(&mut &data[1..], &sig)?);
+
+        // 预哈希签名与普通 Ed25519 签名互不通用
+        let plain = Ed25519Verifier::load("fixtures/ed25519.pk")?;
+        assert!(!plain.verify(&mut &data[..], &sig)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_blake3_streaming_matches_one_shot() -> Result<()> {
+        let blake3 = Blake3::load("fixtures/blake3.txt")?;
+        let data = vec![7u8; 300 * 1024];
+        let sig = blake3.sign(&mut &data[..])?;
+        assert_eq!(sig, blake3::keyed_hash(&blake3.key, &data).as_bytes());
+        assert!(!blake3.verify(&mut &data[1..], &sig)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_detached_signature_roundtrip() -> Result<()> {
+        let sk = fs::read("fixtures/ed25519.sk")?;
+        let pk = fs::read("fixtures/ed25519.pk")?;
+        let data = b"release artifact";
+
+        let sig = process_text_sign_detached(&mut &data[..], &sk, TextSignFormat::Ed25519ph)?;
+        assert_eq!(sig.algorithm, "ed25519ph");
+        let json = serde_json::to_string(&sig)?;
+        let sig: DetachedSignature = serde_json::from_str(&json)?;
+
+        assert!(process_text_verify_detached(
+            &mut &data[..],
+            &pk,
+            &sig,
+            None
+        )?);
+        assert!(!process_text_verify_detached(
+            &mut &b"tampered"[..],
+            &pk,
+            &sig,
+            Some(TextSignFormat::Ed25519ph)
+        )?);
+        // 算法不匹配时直接报错
+        assert!(process_text_verify_detached(
+            &mut &data[..],
+            &pk,
+            &sig,
+            Some(TextSignFormat::Ed25519)
+        )
+        .is_err());
+        // 错误的公钥通过指纹识别
+        let other = [9u8; 32];
+        let other = SigningKey::from_bytes(&other).verifying_key();
+        assert!(
+            process_text_verify_detached(&mut &data[..], other.as_bytes(), &sig, None).is_err()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_ed25519_with_trailing_newline() -> Result<()> {
         // Test that keys with trailing newlines are handled correctly
@@ -367,6 +1272,139 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_blake3_generated_keys_sign_and_verify() -> Result<()> {
+        let data = b"generated key";
+        for encoding in [KeyEncoding::Hex, KeyEncoding::Base64, KeyEncoding::Raw] {
+            let keys = process_text_key_generate(TextSignFormat::Blake3, KeyFormat::Raw, encoding)?;
+            let key = &keys["blake3.txt"];
+            let sig = process_text_sign(&mut &data[..], key, TextSignFormat::Blake3)?;
+            assert!(process_text_verify(
+                &mut &data[..],
+                key,
+                &sig,
+                TextSignFormat::Blake3
+            )?);
+        }
+        let keys =
+            process_text_key_generate(TextSignFormat::Blake3, KeyFormat::Raw, KeyEncoding::Hex)?;
+        assert_eq!(keys["blake3.txt"].len(), 65);
+        assert_ne!(Blake3::generate()?, Blake3::generate()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hmac_matches_rfc4231() -> Result<()> {
+        // RFC 4231 test case 2；密钥文件末尾的换行不参与计算
+        let data = b"what do ya want for nothing?";
+        let sig = process_text_sign(&mut &data[..], b"Jefe\n", TextSignFormat::HmacSha256)?;
+        assert_eq!(
+            hex::encode(&sig),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        let sig = process_text_sign(&mut &data[..], b"Jefe", TextSignFormat::HmacSha512)?;
+        assert!(hex::encode(&sig).starts_with("164b7a7bfcf819e2e395fbe73b56e0a387bd6422"));
+        assert!(process_text_verify(
+            &mut &data[..],
+            b"Jefe",
+            &sig,
+            TextSignFormat::HmacSha512
+        )?);
+        assert!(!process_text_verify(
+            &mut &data[..],
+            b"Jeff",
+            &sig,
+            TextSignFormat::HmacSha512
+        )?);
+        assert!(HmacSha256::try_new(b"\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generated_keys_sign_and_verify() -> Result<()> {
+        let data = vec![5u8; 100 * 1024];
+        for format in [
+            TextSignFormat::HmacSha256,
+            TextSignFormat::HmacSha512,
+            TextSignFormat::EcdsaP256,
+            TextSignFormat::EcdsaSecp256k1,
+        ] {
+            for key_format in [KeyFormat::Raw, KeyFormat::Pem] {
+                let keys = process_text_key_generate(format, key_format, KeyEncoding::Hex)?;
+                let (sk, pk) = match key_pair_files(format, key_format) {
+                    Some((sk, pk)) => (&keys[sk], &keys[pk]),
+                    None => (&keys["hmac.txt"], &keys["hmac.txt"]),
+                };
+                let sig = process_text_sign_detached(&mut &data[..], sk, format)?;
+                assert_eq!(sig.algorithm.parse::<TextSignFormat>()?, format);
+                match format {
+                    TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512 => {
+                        assert_eq!(sig.key_id, None)
+                    }
+                    _ => assert_eq!(
+                        sig.key_id,
+                        Some(process_text_key_fingerprint(pk, format, false)?)
+                    ),
+                }
+                assert!(process_text_verify_detached(
+                    &mut &data[..],
+                    pk,
+                    &sig,
+                    Some(format)
+                )?);
+                assert!(!process_text_verify_detached(
+                    &mut &data[1..],
+                    pk,
+                    &sig,
+                    None
+                )?);
+            }
+        }
+        assert!(process_text_key_generate(
+            TextSignFormat::EcdsaP256,
+            KeyFormat::OpenSsh,
+            KeyEncoding::Hex
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ecdsa_rejects_other_curve_and_public_signing_key() -> Result<()> {
+        let keys =
+            process_text_key_generate(TextSignFormat::EcdsaP256, KeyFormat::Raw, KeyEncoding::Hex)?;
+        let data = b"curve";
+        let sig = process_text_sign(&mut &data[..], &keys["p256.sk"], TextSignFormat::EcdsaP256)?;
+        assert_eq!(sig.len(), 64);
+        // 同一 32 字节标量在 secp256k1 上对应另一把公钥
+        assert!(!process_text_verify(
+            &mut &data[..],
+            &keys["p256.sk"],
+            &sig,
+            TextSignFormat::EcdsaSecp256k1
+        )?);
+        assert!(
+            process_text_sign(&mut &data[..], &keys["p256.pk"], TextSignFormat::EcdsaP256).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_blake3_accepts_encoded_keys() -> Result<()> {
+        let raw = [7u8; 32];
+        let expected = TextSigner::key_id(&Blake3::new(raw));
+        for encoded in [
+            hex::encode(raw),
+            format!("{}\n", hex::encode(raw)),
+            STANDARD.encode(raw),
+            STANDARD_NO_PAD.encode(raw),
+        ] {
+            assert_eq!(TextSigner::key_id(&Blake3::try_new(&encoded)?), expected);
+        }
+        assert_eq!(TextSigner::key_id(&Blake3::try_new(raw)?), expected);
+        Ok(())
+    }
+
     #[test]
     fn test_blake3_with_extra_bytes() -> Result<()> {
         // Test that Blake3 keys handle extra bytes correctly
@@ -386,21 +1424,261 @@ mod tests {
 
     #[test]
     fn test_chacha_encrypt_decrypt_roundtrip() -> Result<()> {
-        let key = b"some passphrase";
+        let key = EncryptionKey::Passphrase(b"some passphrase".to_vec());
+        let mut reader = &b"secret message"[..];
+        let ciphertext = process_text_encrypt(
+            &mut reader,
+            &key,
+            TextCipher::ChaCha20Poly1305,
+            &FAST_KDF,
+            None,
+        )?;
+        assert!(ciphertext.starts_with(b"RSTE"));
+        let plaintext = process_text_decrypt(&ciphertext, &key, None, None)?;
+        assert_eq!(*plaintext, b"secret message");
+
+        // 每条消息使用随机盐，相同口令与明文的输出不同
         let mut reader = &b"secret message"[..];
-        let ciphertext = process_text_encrypt(&mut reader, key)?;
-        assert!(ciphertext.len() > 12);
-        let plaintext = process_text_decrypt(&ciphertext, key)?;
-        assert_eq!(plaintext, b"secret message");
+        let again = process_text_encrypt(
+            &mut reader,
+            &key,
+            TextCipher::ChaCha20Poly1305,
+            &FAST_KDF,
+            None,
+        )?;
+        // KdfSpec 含随机盐
+        let kdf =
+            |data: &[u8]| -> Result<KdfSpec> { Ok(CipherHeader::parse(data)?.unwrap().0.kdf) };
+        assert_ne!(kdf(&ciphertext)?, kdf(&again)?);
+
+        // 篡改密文末字节（认证标签）后解密失败，与头部布局无关
+        let mut tampered = ciphertext.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(process_text_decrypt(&tampered, &key, None, None).is_err());
         Ok(())
     }
-
     #[test]
     fn test_chacha_decrypt_wrong_key() {
-        let key = b"correct key";
+        let key = EncryptionKey::Passphrase(b"correct key".to_vec());
         let mut reader = &b"payload"[..];
-        let ciphertext = process_text_encrypt(&mut reader, key).expect("encrypt");
-        let result = process_text_decrypt(&ciphertext, b"wrong key");
+        let ciphertext = process_text_encrypt(
+            &mut reader,
+            &key,
+            TextCipher::ChaCha20Poly1305,
+            &FAST_KDF,
+            None,
+        )
+        .expect("encrypt");
+        let wrong = EncryptionKey::Passphrase(b"wrong key".to_vec());
+        let result = process_text_decrypt(&ciphertext, &wrong, None, None);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_encrypt_raw_key_bypasses_kdf() -> Result<()> {
+        let key = EncryptionKey::raw_from_bytes(hex::encode([7u8; 32]).as_bytes())?;
+        let mut reader = &b"payload"[..];
+        let ciphertext = process_text_encrypt(
+            &mut reader,
+            &key,
+            TextCipher::ChaCha20Poly1305,
+            &KdfParams::default(),
+            None,
+        )?;
+        assert_eq!(
+            CipherHeader::parse(&ciphertext)?.unwrap().0.kdf,
+            KdfSpec::Raw
+        );
+        assert_eq!(
+            *process_text_decrypt(&ciphertext, &key, None, None)?,
+            b"payload"
+        );
+        let passphrase = EncryptionKey::Passphrase(vec![7u8; 32]);
+        assert!(process_text_decrypt(&ciphertext, &passphrase, None, None).is_err());
+        assert!(EncryptionKey::raw_from_bytes(b"too short").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampered_header() -> Result<()> {
+        let key = EncryptionKey::Passphrase(b"pass".to_vec());
+        let mut reader = &b"payload"[..];
+        let mut ciphertext = process_text_encrypt(
+            &mut reader,
+            &key,
+            TextCipher::ChaCha20Poly1305,
+            &FAST_KDF,
+            None,
+        )?;
+        // 头部参与认证，修改迭代次数会导致解密失败
+        ciphertext[12] ^= 1;
+        assert!(process_text_decrypt(&ciphertext, &key, None, None).is_err());
+        ciphertext[12] ^= 1;
+        ciphertext[4] = 9;
+        assert!(process_text_decrypt(&ciphertext, &key, None, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypt_records_cipher() -> Result<()> {
+        let key = EncryptionKey::Raw([4u8; 32]);
+        for cipher in ALL_CIPHERS {
+            let mut reader = &b"payload"[..];
+            let ciphertext = process_text_encrypt(&mut reader, &key, cipher, &FAST_KDF, None)?;
+            let (header, _) = CipherHeader::parse(&ciphertext)?.unwrap();
+            assert_eq!(
+                (header.cipher, header.nonce.len()),
+                (cipher, cipher.nonce_len())
+            );
+            // 解密时自动选择算法，指定 --cipher 时必须一致
+            assert_eq!(
+                *process_text_decrypt(&ciphertext, &key, None, None)?,
+                b"payload"
+            );
+            assert_eq!(
+                *process_text_decrypt(&ciphertext, &key, Some(cipher), None)?,
+                b"payload"
+            );
+            let other = ALL_CIPHERS.into_iter().find(|c| *c != cipher);
+            assert!(process_text_decrypt(&ciphertext, &key, other, None).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_encrypt_roundtrip() -> Result<()> {
+        let key = EncryptionKey::Raw([1u8; 32]);
+        let segment = DEFAULT_SEGMENT_SIZE as usize;
+        let lens = [0, 10, segment, 2 * segment + 123];
+        for (cipher, len) in ALL_CIPHERS
+            .into_iter()
+            .flat_map(|c| lens.map(|len| (c, len)))
+        {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut sealed = Vec::new();
+            process_text_encrypt_stream(
+                &mut &plaintext[..],
+                &mut sealed,
+                &key,
+                cipher,
+                &KdfParams::default(),
+                None,
+            )?;
+            let mut opened = Vec::new();
+            process_text_decrypt_stream(&mut &sealed[..], &mut opened, &key, Some(cipher), None)?;
+            assert_eq!(opened, plaintext);
+            // 内存解密同样识别分段格式
+            assert_eq!(*process_text_decrypt(&sealed, &key, None, None)?, plaintext);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypt_binds_associated_data() -> Result<()> {
+        let key = EncryptionKey::Raw([6u8; 32]);
+        let cipher = TextCipher::XChaCha20Poly1305;
+        let aad = Some(&b"tenant-42/report.csv"[..]);
+        let mut reader = &b"payload"[..];
+        let ciphertext = process_text_encrypt(&mut reader, &key, cipher, &FAST_KDF, aad)?;
+        assert_eq!(
+            *process_text_decrypt(&ciphertext, &key, None, aad)?,
+            b"payload"
+        );
+        // 换到其他上下文或缺少关联数据都无法解密
+        let other = Some(&b"tenant-7/report.csv"[..]);
+        assert!(process_text_decrypt(&ciphertext, &key, None, other).is_err());
+        assert!(process_text_decrypt(&ciphertext, &key, None, None).is_err());
+
+        let mut sealed = Vec::new();
+        process_text_encrypt_stream(
+            &mut &b"streamed"[..],
+            &mut sealed,
+            &key,
+            cipher,
+            &FAST_KDF,
+            aad,
+        )?;
+        let mut opened = Vec::new();
+        process_text_decrypt_stream(&mut &sealed[..], &mut opened, &key, None, aad)?;
+        assert_eq!(opened, b"streamed");
+        assert!(process_text_decrypt(&sealed, &key, None, other).is_err());
+
+        // 未绑定关联数据的密文不接受 --aad
+        let mut reader = &b"payload"[..];
+        let plain = process_text_encrypt(&mut reader, &key, cipher, &FAST_KDF, None)?;
+        assert!(process_text_decrypt(&plain, &key, None, aad).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_detects_truncation_and_reordering() -> Result<()> {
+        let key = EncryptionKey::Raw([2u8; 32]);
+        let segment = DEFAULT_SEGMENT_SIZE as usize + TAG_LEN;
+        let plaintext = vec![7u8; 3 * DEFAULT_SEGMENT_SIZE as usize];
+        let mut sealed = Vec::new();
+        process_text_encrypt_stream(
+            &mut &plaintext[..],
+            &mut sealed,
+            &key,
+            TextCipher::ChaCha20Poly1305,
+            &FAST_KDF,
+            None,
+        )?;
+        let header_len = CipherHeader::parse(&sealed)?.unwrap().1;
+        assert_eq!(sealed.len(), header_len + 3 * segment);
+
+        // 在分段边界处截断
+        let truncated = &sealed[..header_len + 2 * segment];
+        assert!(process_text_decrypt(truncated, &key, None, None).is_err());
+
+        // 交换前两个分段
+        let mut reordered = sealed[..header_len].to_vec();
+        reordered.extend_from_slice(&sealed[header_len + segment..header_len + 2 * segment]);
+        reordered.extend_from_slice(&sealed[header_len..header_len + segment]);
+        reordered.extend_from_slice(&sealed[header_len + 2 * segment..]);
+        assert!(process_text_decrypt(&reordered, &key, None, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt_legacy_ciphertext() -> Result<()> {
+        let nonce = [1u8; 12];
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(blake3::hash(b"hello").as_bytes()));
+        let mut legacy = nonce.to_vec();
+        legacy.extend(
+            cipher
+                .encrypt(Nonce::from_slice(&nonce), &b"old data"[..])
+                .unwrap(),
+        );
+        let key = EncryptionKey::Passphrase(b"hello".to_vec());
+        assert_eq!(
+            *process_text_decrypt(&legacy, &key, None, None)?,
+            b"old data"
+        );
+
+        // version 1 头部（无 cipher 与 flags 字段）
+        let header = CipherHeader {
+            version: 1,
+            ..CipherHeader::new(
+                TextCipher::ChaCha20Poly1305,
+                KdfSpec::Raw,
+                0,
+                nonce.to_vec(),
+            )
+        };
+        let mut v1 = header.encode();
+        let payload = Payload {
+            msg: &b"v1 data"[..],
+            aad: &v1,
+        };
+        let sealed = ChaCha20Poly1305::new(Key::from_slice(&[5u8; 32]))
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .unwrap();
+        v1.extend(sealed);
+        assert_eq!(
+            *process_text_decrypt(&v1, &EncryptionKey::Raw([5u8; 32]), None, None)?,
+            b"v1 data"
+        );
+        Ok(())
+    }
 }
@@ -1,6 +1,7 @@
 use anyhow::Result;
-use std::fs::File;
-use std::io::{self, Read};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
 
 /// 根据输入参数获取相应的 Reader
 ///
@@ -18,6 +19,41 @@ pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
     }
 }
 
+/// 根据输出参数获取相应的 Writer
+///
+/// # Arguments
+/// * `output` - 输出目标，`None` 或 "-" 表示标准输出，否则为文件路径（会被覆盖）
+///
+/// # Returns
+/// * `Result<Box<dyn Write>>` - 返回一个带缓冲、实现了 Write trait 的 Box
+pub fn get_writer(output: Option<&str>) -> Result<Box<dyn Write>> {
+    match output {
+        None | Some("-") => Ok(Box::new(BufWriter::new(io::stdout()))),
+        Some(path) => {
+            let file = File::create(path)?;
+            Ok(Box::new(BufWriter::new(file)))
+        }
+    }
+}
+
+/// 写入私钥等敏感文件，Unix 下权限为 0600（OpenSSH 等工具会拒绝权限过宽的私钥）
+///
+/// # Arguments
+/// * `path` - 目标文件路径（会被覆盖）
+/// * `data` - 文件内容
+pub fn write_secret_file(path: impl AsRef<Path>, data: &[u8]) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(data)?;
+    Ok(())
+}
+
 /// 获取输入的字节数据
 ///
 /// # Arguments